@@ -1,20 +1,32 @@
 use std::marker::PhantomData;
 
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
 
-use crate::api::ws::WsRequest;
+use crate::{
+    api::ws::{RequestId, WsRequest, WsResponse},
+    Result,
+};
 //submodules
+pub mod account;
+pub mod decimal;
+pub mod error_code;
+pub mod general;
+pub mod market;
 pub mod order;
+pub mod order_list;
 pub mod secret_key;
 pub mod subscription;
 pub mod types;
+pub mod user_data;
 pub mod utils;
 pub mod ws;
 
 pub struct ProcessedRequest<T: ?Sized> {
-    pub(crate) id: Uuid,
+    pub(crate) id: RequestId,
     pub(crate) text: String,
+    pub(crate) method: &'static str,
+    pub(crate) weight: u32,
+    pub(crate) orders: u32,
     pub(crate) marker: PhantomData<T>,
 }
 
@@ -23,15 +35,52 @@ pub trait BinanceRequest: Serialize {
 
     const METHOD: &'static str;
 
-    fn preprocess(self) -> Result<ProcessedRequest<Self>, serde_json::Error>
+    /// Request weight counted towards the REQUEST_WEIGHT limit
+    const WEIGHT: u32 = 1;
+
+    /// Amount of orders counted towards the ORDERS limit
+    const ORDERS: u32 = 0;
+
+    /// Method sent on the wire, for requests that pick it at runtime
+    fn method(&self) -> &'static str {
+        Self::METHOD
+    }
+
+    /// Weight of this particular request, for requests weighted by their parameters
+    fn weight(&self) -> u32 {
+        Self::WEIGHT
+    }
+
+    /// Orders counted for this particular request
+    fn orders(&self) -> u32 {
+        Self::ORDERS
+    }
+
+    /// Id of a new request of this type
+    fn request_id() -> RequestId {
+        RequestId::new_uuid()
+    }
+
+    /// Decode the frame answering this request
+    fn decode(bytes: &[u8]) -> Result<WsResponse<Self::Response>> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    fn preprocess(self) -> Result<ProcessedRequest<Self>>
     where
         Self: Sized,
     {
+        let method = self.method();
+        let weight = self.weight();
+        let orders = self.orders();
         let ws_request = WsRequest::new(self);
         let text = serde_json::to_string(&ws_request)?;
         Ok(ProcessedRequest {
             id: ws_request.id,
             text,
+            method,
+            weight,
+            orders,
             marker: PhantomData,
         })
     }
@@ -48,6 +97,30 @@ where
     type Response = T::Response;
 
     const METHOD: &'static str = T::METHOD;
+
+    const WEIGHT: u32 = T::WEIGHT;
+
+    const ORDERS: u32 = T::ORDERS;
+
+    fn method(&self) -> &'static str {
+        (*self).method()
+    }
+
+    fn weight(&self) -> u32 {
+        (*self).weight()
+    }
+
+    fn orders(&self) -> u32 {
+        (*self).orders()
+    }
+
+    fn request_id() -> RequestId {
+        T::request_id()
+    }
+
+    fn decode(bytes: &[u8]) -> Result<WsResponse<Self::Response>> {
+        T::decode(bytes)
+    }
 }
 // RequestPayload is a subtrait of BinanceRequest so whatever
 // implements RequestPayload must implement BinanceRequest
@@ -56,7 +129,29 @@ pub trait RequestPayload: BinanceRequest {
 
     fn has_signature(&self) -> bool;
 
-    fn payload(&self) -> String;
+    fn payload(&self) -> Result<String>;
 }
 
 pub trait BinanceOkResponse<'de>: Deserialize<'de> {}
+
+impl<'de, T: BinanceOkResponse<'de>> BinanceOkResponse<'de> for Vec<T> {}
+
+/// Implement `RequestPayload` for requests signed through their `signature` field
+macro_rules! signed_request {
+    ($($request:ty),* $(,)?) => {$(
+        impl $crate::api::RequestPayload for $request {
+            fn set_signature(&mut self, signature: String) {
+                self.signature = Some(signature);
+            }
+
+            fn has_signature(&self) -> bool {
+                self.signature.is_some()
+            }
+
+            fn payload(&self) -> $crate::Result<String> {
+                Ok(serde_qs::to_string(&self)?)
+            }
+        }
+    )*};
+}
+pub(crate) use signed_request;
@@ -0,0 +1,177 @@
+use super::{
+    decimal::{Decimal, Qty},
+    signed_request,
+    types::RateLimit,
+    utils, BinanceOkResponse, BinanceRequest,
+};
+use pyo3::pyclass;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountStatusRequest {
+    pub api_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub omit_zero_balances: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recv_window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub timestamp: u64,
+}
+
+impl AccountStatusRequest {
+    pub fn new(api_key: &str) -> Self {
+        AccountStatusRequest {
+            api_key: api_key.into(),
+            timestamp: utils::server_timestamp(),
+            omit_zero_balances: Default::default(),
+            recv_window: Default::default(),
+            signature: Default::default(),
+        }
+    }
+}
+
+impl BinanceRequest for AccountStatusRequest {
+    type Response = AccountStatusResponse;
+
+    const METHOD: &'static str = "account.status";
+
+    const WEIGHT: u32 = 20;
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountCommissionRequest {
+    pub api_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recv_window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub symbol: String,
+    pub timestamp: u64,
+}
+
+impl AccountCommissionRequest {
+    pub fn new(api_key: &str, symbol: &str) -> Self {
+        AccountCommissionRequest {
+            api_key: api_key.into(),
+            symbol: symbol.into(),
+            timestamp: utils::server_timestamp(),
+            recv_window: Default::default(),
+            signature: Default::default(),
+        }
+    }
+}
+
+impl BinanceRequest for AccountCommissionRequest {
+    type Response = AccountCommissionResponse;
+
+    const METHOD: &'static str = "account.commission";
+
+    const WEIGHT: u32 = 20;
+}
+
+/// Unfilled order counts for the ORDERS limits
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountRateLimitsOrdersRequest {
+    pub api_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recv_window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub timestamp: u64,
+}
+
+impl AccountRateLimitsOrdersRequest {
+    pub fn new(api_key: &str) -> Self {
+        AccountRateLimitsOrdersRequest {
+            api_key: api_key.into(),
+            timestamp: utils::server_timestamp(),
+            recv_window: Default::default(),
+            signature: Default::default(),
+        }
+    }
+}
+
+impl<'de> BinanceOkResponse<'de> for RateLimit {}
+
+impl BinanceRequest for AccountRateLimitsOrdersRequest {
+    type Response = Vec<RateLimit>;
+
+    const METHOD: &'static str = "account.rateLimits.orders";
+
+    const WEIGHT: u32 = 40;
+}
+
+signed_request!(
+    AccountStatusRequest,
+    AccountCommissionRequest,
+    AccountRateLimitsOrdersRequest,
+);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[pyclass]
+pub struct Balance {
+    // the user data stream uses short names
+    #[pyo3(get)]
+    #[serde(alias = "a")]
+    pub asset: String,
+    #[pyo3(get)]
+    #[serde(alias = "f")]
+    pub free: Qty,
+    #[pyo3(get)]
+    #[serde(alias = "l")]
+    pub locked: Qty,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountStatusResponse {
+    pub maker_commission: u32,
+    pub taker_commission: u32,
+    pub buyer_commission: u32,
+    pub seller_commission: u32,
+    pub can_trade: bool,
+    pub can_withdraw: bool,
+    pub can_deposit: bool,
+    pub update_time: u64,
+    pub account_type: String,
+    pub balances: Vec<Balance>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+impl<'de> BinanceOkResponse<'de> for AccountStatusResponse {}
+
+/// Commission rates as fractions, e.g. "0.00100000"
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommissionRates {
+    pub maker: Decimal,
+    pub taker: Decimal,
+    pub buyer: Decimal,
+    pub seller: Decimal,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommissionDiscount {
+    pub enabled_for_account: bool,
+    pub enabled_for_symbol: bool,
+    pub discount_asset: String,
+    pub discount: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountCommissionResponse {
+    pub symbol: String,
+    pub standard_commission: CommissionRates,
+    pub tax_commission: CommissionRates,
+    pub discount: CommissionDiscount,
+}
+
+impl<'de> BinanceOkResponse<'de> for AccountCommissionResponse {}
@@ -0,0 +1,532 @@
+use std::{
+    cmp::Ordering,
+    fmt::{self, Write},
+    hash::{Hash, Hasher},
+    ops::{Add, Deref, Neg, Sub},
+    str::FromStr,
+};
+
+use pyo3::{IntoPy, PyObject, Python};
+use serde::{
+    de::{self, Unexpected, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+/// Decimals Binance quotes prices and quantities with
+pub const SCALE: u8 = 8;
+
+const ONE: i64 = 10i64.pow(SCALE as u32);
+
+/// Fixed-point decimal in units of 1e-8, as Binance sends prices and quantities.
+///
+/// It is formatted with the decimals it was parsed with, so `"0.01000000"` goes back
+/// on the wire as is, but compares by value only.
+#[derive(Clone, Copy, Default)]
+pub struct Decimal {
+    units: i64,
+    /// Decimals shown at least when formatted
+    decimals: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("invalid decimal, expected digits with at most {SCALE} significant decimals")]
+pub struct ParseDecimalError;
+
+impl Decimal {
+    pub const ZERO: Decimal = Decimal::from_units(0);
+    pub const ONE: Decimal = Decimal::from_units(ONE);
+
+    /// Decimal of `units` times 1e-8
+    pub const fn from_units(units: i64) -> Self {
+        Decimal { units, decimals: 0 }
+    }
+
+    pub const fn units(&self) -> i64 {
+        self.units
+    }
+
+    /// Nearest decimal of a float, None if not finite or out of range
+    pub fn from_f64(value: f64) -> Option<Self> {
+        let units = (value * ONE as f64).round();
+        // the bound itself rounds up to 2^63 which no longer fits
+        if !units.is_finite() || units.abs() >= i64::MAX as f64 {
+            return None;
+        }
+        Some(Decimal::from_units(units as i64))
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.units as f64 / ONE as f64
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.units == 0
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.units > 0
+    }
+
+    /// Least decimals showing the value exactly
+    fn significant_decimals(&self) -> u8 {
+        let mut fraction = self.units.unsigned_abs() % ONE as u64;
+        if fraction == 0 {
+            return 0;
+        }
+        let mut decimals = SCALE;
+        while fraction.is_multiple_of(10) {
+            fraction /= 10;
+            decimals -= 1;
+        }
+        decimals
+    }
+
+    /// Show at least `decimals` decimals, capped at the scale. Never rounds, the
+    /// significant ones are always shown.
+    pub fn with_decimals(self, decimals: u8) -> Self {
+        Decimal {
+            decimals: decimals.min(SCALE),
+            ..self
+        }
+    }
+
+    /// Drop the trailing zeros it was parsed with
+    pub fn normalize(self) -> Self {
+        self.with_decimals(0)
+    }
+
+    /// Round to a multiple of `step`, down or up, shown with the decimals of the step.
+    /// Left as is by a step that is not positive.
+    pub fn round_to(self, step: Decimal, up: bool) -> Self {
+        if !step.is_positive() {
+            return self;
+        }
+        let mut steps = self.units.div_euclid(step.units);
+        if up && self.units.rem_euclid(step.units) != 0 {
+            steps += 1;
+        }
+        Decimal {
+            units: steps.saturating_mul(step.units),
+            decimals: step.significant_decimals().max(step.decimals),
+        }
+    }
+
+    /// Sum shown with the most decimals of both, None on overflow
+    pub fn checked_add(self, other: Decimal) -> Option<Self> {
+        Some(Decimal {
+            units: self.units.checked_add(other.units)?,
+            decimals: self.decimals.max(other.decimals),
+        })
+    }
+
+    /// Difference shown with the most decimals of both, None on overflow
+    pub fn checked_sub(self, other: Decimal) -> Option<Self> {
+        Some(Decimal {
+            units: self.units.checked_sub(other.units)?,
+            decimals: self.decimals.max(other.decimals),
+        })
+    }
+
+    /// Product truncated to the scale, e.g. the notional of a price and a quantity
+    pub fn checked_mul(self, other: Decimal) -> Option<Self> {
+        let units = self.units as i128 * other.units as i128 / ONE as i128;
+        Some(Decimal {
+            units: units.try_into().ok()?,
+            decimals: self.decimals.saturating_add(other.decimals).min(SCALE),
+        })
+    }
+
+    /// Quotient truncated to the scale, e.g. the quantity a quote amount buys at a
+    /// price. None when dividing by zero or on overflow.
+    pub fn checked_div(self, other: Decimal) -> Option<Self> {
+        if other.is_zero() {
+            return None;
+        }
+        let units = self.units as i128 * ONE as i128 / other.units as i128;
+        Some(Decimal::from_units(units.try_into().ok()?))
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if integer.is_empty() && fraction.is_empty() {
+            return Err(ParseDecimalError);
+        }
+
+        let mut units = 0i64;
+        for byte in integer.bytes() {
+            if !byte.is_ascii_digit() {
+                return Err(ParseDecimalError);
+            }
+            units = units
+                .checked_mul(10)
+                .and_then(|units| units.checked_add((byte - b'0') as i64))
+                .ok_or(ParseDecimalError)?;
+        }
+        units = units.checked_mul(ONE).ok_or(ParseDecimalError)?;
+
+        let mut unit = ONE;
+        for (index, byte) in fraction.bytes().enumerate() {
+            if !byte.is_ascii_digit() || (index >= SCALE as usize && byte != b'0') {
+                return Err(ParseDecimalError);
+            }
+            unit /= 10;
+            units = units
+                .checked_add((byte - b'0') as i64 * unit)
+                .ok_or(ParseDecimalError)?;
+        }
+
+        Ok(Decimal {
+            units: if negative { -units } else { units },
+            decimals: fraction.len().min(SCALE as usize) as u8,
+        })
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let decimals = self.decimals.max(self.significant_decimals());
+        let units = self.units.unsigned_abs();
+        let sign = if self.units < 0 { "-" } else { "" };
+        let integer = units / ONE as u64;
+        if decimals == 0 {
+            return write!(f, "{sign}{integer}");
+        }
+        let fraction = units % ONE as u64 / 10u64.pow((SCALE - decimals) as u32);
+        write!(
+            f,
+            "{sign}{integer}.{fraction:0width$}",
+            width = decimals as usize
+        )
+    }
+}
+
+impl fmt::Debug for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.units == other.units
+    }
+}
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.units.cmp(&other.units)
+    }
+}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.units.hash(state);
+    }
+}
+
+/// Saturates at the bounds, see `checked_add` to catch the overflow
+impl Add for Decimal {
+    type Output = Decimal;
+
+    fn add(self, other: Decimal) -> Decimal {
+        Decimal {
+            units: self.units.saturating_add(other.units),
+            decimals: self.decimals.max(other.decimals),
+        }
+    }
+}
+
+/// Saturates at the bounds, see `checked_sub` to catch the overflow
+impl Sub for Decimal {
+    type Output = Decimal;
+
+    fn sub(self, other: Decimal) -> Decimal {
+        Decimal {
+            units: self.units.saturating_sub(other.units),
+            decimals: self.decimals.max(other.decimals),
+        }
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Decimal;
+
+    fn neg(self) -> Decimal {
+        Decimal {
+            units: self.units.saturating_neg(),
+            ..self
+        }
+    }
+}
+
+/// Wraps a decimal into a type of its own, serialized and handed to Python the same
+macro_rules! decimal_newtype {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(pub Decimal);
+
+        impl $name {
+            pub const ZERO: $name = $name(Decimal::ZERO);
+
+            /// Nearest value of a float, None if not finite or out of range
+            pub fn from_f64(value: f64) -> Option<Self> {
+                Decimal::from_f64(value).map($name)
+            }
+        }
+
+        impl Deref for $name {
+            type Target = Decimal;
+
+            fn deref(&self) -> &Decimal {
+                &self.0
+            }
+        }
+
+        impl From<Decimal> for $name {
+            fn from(decimal: Decimal) -> Self {
+                $name(decimal)
+            }
+        }
+
+        impl From<$name> for Decimal {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseDecimalError;
+
+            fn from_str(text: &str) -> Result<Self, Self::Err> {
+                text.parse().map($name)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
+
+        impl IntoPy<PyObject> for $name {
+            fn into_py(self, py: Python<'_>) -> PyObject {
+                self.0.into_py(py)
+            }
+        }
+    };
+}
+
+decimal_newtype!(
+    /// Price of a symbol, in quote asset per unit of the base asset
+    Price
+);
+decimal_newtype!(
+    /// Quantity of an asset, as an order amount, a fill or a balance
+    Qty
+);
+
+/// Stack buffer a decimal is formatted into, the longest takes 21 bytes
+struct Buffer {
+    bytes: [u8; 24],
+    len: usize,
+}
+
+impl Write for Buffer {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        let end = self.len + text.len();
+        self.bytes
+            .get_mut(self.len..end)
+            .ok_or(fmt::Error)?
+            .copy_from_slice(text.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut buffer = Buffer {
+            bytes: [0; 24],
+            len: 0,
+        };
+        write!(buffer, "{self}").map_err(serde::ser::Error::custom)?;
+        // only ASCII was written
+        let text = std::str::from_utf8(&buffer.bytes[..buffer.len]).unwrap();
+        serializer.serialize_str(text)
+    }
+}
+
+struct DecimalVisitor;
+
+impl<'de> Visitor<'de> for DecimalVisitor {
+    type Value = Decimal;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a decimal with at most {SCALE} decimals")
+    }
+
+    fn visit_str<E: de::Error>(self, text: &str) -> Result<Decimal, E> {
+        text.parse()
+            .map_err(|_| E::invalid_value(Unexpected::Str(text), &self))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Decimal, E> {
+        i64::try_from(value)
+            .ok()
+            .and_then(|value| value.checked_mul(ONE))
+            .map(Decimal::from_units)
+            .ok_or_else(|| E::invalid_value(Unexpected::Unsigned(value), &self))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Decimal, E> {
+        value
+            .checked_mul(ONE)
+            .map(Decimal::from_units)
+            .ok_or_else(|| E::invalid_value(Unexpected::Signed(value), &self))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Decimal, E> {
+        Decimal::from_f64(value).ok_or_else(|| E::invalid_value(Unexpected::Float(value), &self))
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(DecimalVisitor)
+    }
+}
+
+/// Handed to Python as a float
+impl IntoPy<PyObject> for Decimal {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        self.to_f64().into_py(py)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(text: &str) -> Decimal {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_format() {
+        assert_eq!(decimal("0.01000000").units(), 1_000_000);
+        assert_eq!(decimal("0.01000000").to_string(), "0.01000000");
+        assert_eq!(decimal("52000.00").to_string(), "52000.00");
+        assert_eq!(decimal("-1.5").to_string(), "-1.5");
+        assert_eq!(decimal("-0.5").units(), -50_000_000);
+        assert_eq!(decimal(".5"), decimal("0.5"));
+        assert_eq!(decimal("7").to_string(), "7");
+        assert_eq!(decimal("1.0000000000").to_string(), "1.00000000");
+        assert_eq!(decimal("0.01000000"), decimal("0.01"));
+        assert_eq!(decimal("0.01000000").normalize().to_string(), "0.01");
+        assert_eq!(decimal("1.5").with_decimals(3).to_string(), "1.500");
+
+        for text in ["", ".", "-", "1e5", "0.000000001", "1.2.3", "+1", " 1"] {
+            assert_eq!(text.parse::<Decimal>(), Err(ParseDecimalError), "{text:?}");
+        }
+        assert!("92233720368.54775807".parse::<Decimal>().is_ok());
+        assert!("92233720368.54775808".parse::<Decimal>().is_err());
+        assert_eq!(
+            Decimal::from_units(i64::MIN + 1).to_string(),
+            "-92233720368.54775807"
+        );
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(
+            decimal("0.12345678").round_to(decimal("0.00001000"), false),
+            decimal("0.12345")
+        );
+        let up = decimal("30000.121").round_to(decimal("0.01"), true);
+        assert_eq!(up.to_string(), "30000.13");
+        assert_eq!(decimal("-1.5").round_to(decimal("1"), false), decimal("-2"));
+        assert_eq!(
+            decimal("2").round_to(decimal("0.5"), true).to_string(),
+            "2.0"
+        );
+
+        let notional = decimal("30000.5").checked_mul(decimal("0.002")).unwrap();
+        assert_eq!(notional.to_string(), "60.0010");
+        assert_eq!(
+            decimal("0.5") + decimal("0.25") - decimal("1"),
+            decimal("-0.25")
+        );
+        assert!(decimal("0.1") < decimal("0.10000001"));
+
+        let max = Decimal::from_units(i64::MAX);
+        let min = Decimal::from_units(i64::MIN);
+        assert_eq!(
+            decimal("1.50")
+                .checked_add(decimal("0.125"))
+                .map(|sum| sum.to_string()),
+            Some("1.625".into())
+        );
+        assert_eq!(max.checked_add(decimal("0.00000001")), None);
+        assert_eq!(min.checked_sub(decimal("0.00000001")), None);
+        assert_eq!(max + decimal("1"), max);
+        assert_eq!(min - decimal("1"), min);
+        assert_eq!(-min, max);
+
+        assert_eq!(
+            decimal("100").checked_div(decimal("0.05")),
+            Some(decimal("2000"))
+        );
+        assert_eq!(
+            decimal("1").checked_div(decimal("3")),
+            Some(decimal("0.33333333"))
+        );
+        assert_eq!(decimal("1").checked_div(Decimal::ZERO), None);
+        assert_eq!(max.checked_div(decimal("0.5")), None);
+
+        assert_eq!(Decimal::from_f64(0.1), Some(decimal("0.1")));
+        assert_eq!(Decimal::from_f64(1.0 / 3.0), Some(decimal("0.33333333")));
+        assert_eq!(Decimal::from_f64(f64::NAN), None);
+        assert_eq!(Decimal::from_f64(1e12), None);
+    }
+
+    #[test]
+    fn serde() {
+        let decimals: Vec<Decimal> = serde_json::from_str(r#"["0.00100000", 2, 1.5]"#).unwrap();
+        assert_eq!(decimals, [decimal("0.001"), decimal("2"), decimal("1.5")]);
+        assert_eq!(
+            serde_json::to_string(&decimals).unwrap(),
+            r#"["0.00100000","2","1.5"]"#
+        );
+        assert!(serde_json::from_str::<Decimal>(r#""abc""#).is_err());
+
+        let level: (Price, Qty) = serde_json::from_str(r#"["30000.10", "0.5"]"#).unwrap();
+        assert_eq!(level, (Price(decimal("30000.1")), Qty(decimal("0.5"))));
+        assert_eq!(
+            serde_json::to_string(&level).unwrap(),
+            r#"["30000.10","0.5"]"#
+        );
+    }
+}
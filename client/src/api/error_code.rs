@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+
+macro_rules! error_codes {
+    ($($(#[$meta:meta])* $name:ident = $code:literal,)*) => {
+        /// Error codes of the Binance spot API, see
+        /// https://binance-docs.github.io/apidocs/websocket_api/en/#error-codes
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[serde(from = "i32", into = "i32")]
+        pub enum BinanceErrorCode {
+            $($(#[$meta])* $name,)*
+            /// A code that is not documented (yet)
+            Unknown(i32),
+        }
+
+        impl BinanceErrorCode {
+            pub fn code(&self) -> i32 {
+                match self {
+                    $(BinanceErrorCode::$name => $code,)*
+                    BinanceErrorCode::Unknown(code) => *code,
+                }
+            }
+        }
+
+        impl From<i32> for BinanceErrorCode {
+            fn from(code: i32) -> Self {
+                match code {
+                    $($code => BinanceErrorCode::$name,)*
+                    code => BinanceErrorCode::Unknown(code),
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    UnknownServerError = -1000,
+    Disconnected = -1001,
+    Unauthorized = -1002,
+    /// Too much request weight used
+    TooManyRequests = -1003,
+    UnexpectedResponse = -1006,
+    Timeout = -1007,
+    ServerBusy = -1008,
+    /// Also returned for filter failures such as LOT_SIZE or MIN_NOTIONAL
+    InvalidMessage = -1013,
+    UnknownOrderComposition = -1014,
+    /// Too many new orders
+    TooManyOrders = -1015,
+    ServiceShuttingDown = -1016,
+    UnsupportedOperation = -1020,
+    /// Timestamp outside of the recvWindow
+    InvalidTimestamp = -1021,
+    InvalidSignature = -1022,
+    IllegalChars = -1100,
+    TooManyParameters = -1101,
+    MandatoryParamEmptyOrMalformed = -1102,
+    UnknownParam = -1103,
+    UnreadParameters = -1104,
+    ParamEmpty = -1105,
+    ParamNotRequired = -1106,
+    ParamOverflow = -1108,
+    BadPrecision = -1111,
+    NoDepth = -1112,
+    TifNotRequired = -1114,
+    InvalidTif = -1115,
+    InvalidOrderType = -1116,
+    InvalidSide = -1117,
+    EmptyNewClOrdId = -1118,
+    EmptyOrgClOrdId = -1119,
+    BadInterval = -1120,
+    BadSymbol = -1121,
+    InvalidSymbolStatus = -1122,
+    InvalidListenKey = -1125,
+    MoreThanXxHours = -1127,
+    OptionalParamsBadCombo = -1128,
+    InvalidParameter = -1130,
+    BadStrategyType = -1134,
+    InvalidJson = -1135,
+    InvalidTickerType = -1139,
+    InvalidCancelRestrictions = -1145,
+    DuplicateSymbols = -1151,
+    /// Includes insufficient balance
+    NewOrderRejected = -2010,
+    CancelRejected = -2011,
+    NoSuchOrder = -2013,
+    BadApiKeyFmt = -2014,
+    RejectedMbxKey = -2015,
+    NoTradingWindow = -2016,
+    OrderArchived = -2026,
+}
+
+impl From<BinanceErrorCode> for i32 {
+    fn from(code: BinanceErrorCode) -> Self {
+        code.code()
+    }
+}
+
+impl BinanceErrorCode {
+    /// The request weight or order rate limit was hit, back off before retrying
+    pub fn is_rate_limit(&self) -> bool {
+        matches!(
+            self,
+            BinanceErrorCode::TooManyRequests | BinanceErrorCode::TooManyOrders
+        )
+    }
+
+    /// The local clock drifted from the server, resync before retrying
+    pub fn is_timestamp(&self) -> bool {
+        matches!(self, BinanceErrorCode::InvalidTimestamp)
+    }
+
+    /// The order violated one of the symbol filters
+    pub fn is_filter_failure(&self) -> bool {
+        matches!(self, BinanceErrorCode::InvalidMessage)
+    }
+
+    /// The same request may succeed later, possibly after backing off or resyncing
+    pub fn is_retryable(&self) -> bool {
+        self.is_rate_limit()
+            || self.is_timestamp()
+            || matches!(
+                self,
+                BinanceErrorCode::UnknownServerError
+                    | BinanceErrorCode::Disconnected
+                    | BinanceErrorCode::UnexpectedResponse
+                    | BinanceErrorCode::Timeout
+                    | BinanceErrorCode::ServerBusy
+                    | BinanceErrorCode::ServiceShuttingDown
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BinanceErrorCode;
+    use crate::api::ws::WsError;
+
+    #[test]
+    fn codes() {
+        let error: WsError =
+            serde_json::from_str(r#"{"code":-1021,"msg":"Timestamp outside of recvWindow."}"#)
+                .unwrap();
+        assert_eq!(error.code, BinanceErrorCode::InvalidTimestamp);
+        assert!(error.code.is_retryable());
+
+        assert_eq!(
+            BinanceErrorCode::from(-2010),
+            BinanceErrorCode::NewOrderRejected
+        );
+        assert!(!BinanceErrorCode::NewOrderRejected.is_retryable());
+        assert_eq!(
+            BinanceErrorCode::from(-9999),
+            BinanceErrorCode::Unknown(-9999)
+        );
+        assert_eq!(BinanceErrorCode::Unknown(-9999).code(), -9999);
+        assert_eq!(
+            serde_json::to_string(&BinanceErrorCode::TooManyRequests).unwrap(),
+            "-1003"
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::{
+    types::{OrderType, RateLimit},
+    BinanceOkResponse, BinanceRequest,
+};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TimeRequest {}
+
+impl TimeRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl BinanceRequest for TimeRequest {
+    type Response = TimeResponse;
+
+    const METHOD: &'static str = "time";
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeResponse {
+    pub server_time: u64,
+}
+
+impl<'de> BinanceOkResponse<'de> for TimeResponse {}
+
+/// Result of requests that only report success
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EmptyResponse {}
+
+impl<'de> BinanceOkResponse<'de> for EmptyResponse {}
+
+/// Trading rules of the exchange, for every symbol unless some are asked for
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeInfoRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbols: Option<Vec<String>>,
+    /// Only the symbols tradable with these permissions, e.g. "SPOT"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Vec<String>>,
+}
+
+impl ExchangeInfoRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn symbols(symbols: Vec<String>) -> Self {
+        ExchangeInfoRequest {
+            symbols: Some(symbols),
+            permissions: Default::default(),
+        }
+    }
+
+    pub fn permissions(permissions: Vec<String>) -> Self {
+        ExchangeInfoRequest {
+            symbols: Default::default(),
+            permissions: Some(permissions),
+        }
+    }
+}
+
+impl BinanceRequest for ExchangeInfoRequest {
+    type Response = ExchangeInfoResponse;
+
+    const METHOD: &'static str = "exchangeInfo";
+
+    const WEIGHT: u32 = 20;
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeInfoResponse {
+    pub timezone: String,
+    pub server_time: u64,
+    #[serde(default)]
+    pub rate_limits: Vec<RateLimit>,
+    pub symbols: Vec<SymbolInfo>,
+}
+
+impl<'de> BinanceOkResponse<'de> for ExchangeInfoResponse {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolInfo {
+    pub symbol: String,
+    /// "TRADING" while the symbol can be traded
+    pub status: String,
+    pub base_asset: String,
+    pub base_asset_precision: u32,
+    pub quote_asset: String,
+    pub quote_asset_precision: u32,
+    #[serde(default)]
+    pub order_types: Vec<OrderType>,
+    #[serde(default)]
+    pub is_spot_trading_allowed: bool,
+    pub filters: Vec<SymbolFilter>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+/// Rules an order on a symbol has to follow, values are sent as decimal strings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "filterType", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SymbolFilter {
+    #[serde(rename_all = "camelCase")]
+    PriceFilter {
+        min_price: String,
+        max_price: String,
+        tick_size: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    LotSize {
+        min_qty: String,
+        max_qty: String,
+        step_size: String,
+    },
+    /// Lot size of market orders
+    #[serde(rename_all = "camelCase")]
+    MarketLotSize {
+        min_qty: String,
+        max_qty: String,
+        step_size: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    MinNotional {
+        min_notional: String,
+        apply_to_market: bool,
+        avg_price_mins: u32,
+    },
+    #[serde(rename_all = "camelCase")]
+    Notional {
+        min_notional: String,
+        apply_min_to_market: bool,
+        max_notional: String,
+        apply_max_to_market: bool,
+        avg_price_mins: u32,
+    },
+    /// Filters not enforced client side
+    #[serde(other)]
+    Other,
+}
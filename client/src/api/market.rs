@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::{
+    decimal::{Price, Qty},
+    types::KlineInterval,
+    BinanceOkResponse, BinanceRequest,
+};
+
+/// Price and quantity of a book level
+pub type Level = (Price, Qty);
+
+/// Snapshot of the order book, the starting point of a local book kept up to date
+/// with the `depth` stream
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DepthRequest {
+    pub symbol: String,
+    /// Levels per side, 100 by default and up to 5000
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+impl DepthRequest {
+    pub fn new(symbol: &str, limit: Option<u32>) -> Self {
+        DepthRequest {
+            symbol: symbol.into(),
+            limit,
+        }
+    }
+}
+
+impl BinanceRequest for DepthRequest {
+    type Response = DepthResponse;
+
+    const METHOD: &'static str = "depth";
+
+    const WEIGHT: u32 = 5;
+
+    fn weight(&self) -> u32 {
+        match self.limit.unwrap_or(100) {
+            0..=100 => 5,
+            101..=500 => 25,
+            501..=1000 => 50,
+            _ => 250,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepthResponse {
+    pub last_update_id: u64,
+    pub bids: Vec<Level>,
+    pub asks: Vec<Level>,
+}
+
+impl<'de> BinanceOkResponse<'de> for DepthResponse {}
+
+/// Payload of the `<symbol>@depth` and `<symbol>@depth@100ms` diff streams, levels
+/// with a zero quantity are to be removed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DepthUpdate {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "U")]
+    pub first_update_id: u64,
+    #[serde(rename = "u")]
+    pub final_update_id: u64,
+    #[serde(rename = "b")]
+    pub bids: Vec<Level>,
+    #[serde(rename = "a")]
+    pub asks: Vec<Level>,
+}
+
+impl DepthUpdate {
+    /// Name of the diff stream of a symbol, pushed every 100ms
+    pub fn stream(symbol: &str) -> String {
+        format!("{}@depth@100ms", symbol.to_lowercase())
+    }
+}
+
+/// Candles of a symbol, as `klines` or as `uiKlines` which are tweaked for charts
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KlinesRequest {
+    pub symbol: String,
+    pub interval: KlineInterval,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<u64>,
+    /// Offset the daily and longer candles start at, e.g. "+08:00"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+    /// 500 by default and up to 1000
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// Send as `uiKlines`
+    #[serde(skip)]
+    pub ui: bool,
+}
+
+impl KlinesRequest {
+    pub fn new(symbol: &str, interval: KlineInterval, limit: Option<u32>) -> Self {
+        KlinesRequest {
+            symbol: symbol.into(),
+            interval,
+            start_time: Default::default(),
+            end_time: Default::default(),
+            time_zone: Default::default(),
+            limit,
+            ui: false,
+        }
+    }
+
+    /// The same request sent as `uiKlines`
+    pub fn ui(self) -> Self {
+        KlinesRequest { ui: true, ..self }
+    }
+}
+
+impl BinanceRequest for KlinesRequest {
+    type Response = Vec<Kline>;
+
+    const METHOD: &'static str = "klines";
+
+    const WEIGHT: u32 = 2;
+
+    fn method(&self) -> &'static str {
+        if self.ui {
+            "uiKlines"
+        } else {
+            Self::METHOD
+        }
+    }
+}
+
+/// Kline as sent by Binance, an array of its fields
+type KlineRow = (
+    u64,
+    Price,
+    Price,
+    Price,
+    Price,
+    Qty,
+    u64,
+    Qty,
+    u64,
+    Qty,
+    Qty,
+    String,
+);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(from = "KlineRow", into = "KlineRow")]
+pub struct Kline {
+    pub open_time: u64,
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+    pub volume: Qty,
+    pub close_time: u64,
+    pub quote_volume: Qty,
+    pub trades: u64,
+    pub taker_buy_volume: Qty,
+    pub taker_buy_quote_volume: Qty,
+}
+
+impl<'de> BinanceOkResponse<'de> for Kline {}
+
+impl From<KlineRow> for Kline {
+    fn from(row: KlineRow) -> Self {
+        let (
+            open_time,
+            open,
+            high,
+            low,
+            close,
+            volume,
+            close_time,
+            quote_volume,
+            trades,
+            taker_buy_volume,
+            taker_buy_quote_volume,
+            _unused,
+        ) = row;
+        Kline {
+            open_time,
+            open,
+            high,
+            low,
+            close,
+            volume,
+            close_time,
+            quote_volume,
+            trades,
+            taker_buy_volume,
+            taker_buy_quote_volume,
+        }
+    }
+}
+
+impl From<Kline> for KlineRow {
+    fn from(kline: Kline) -> Self {
+        (
+            kline.open_time,
+            kline.open,
+            kline.high,
+            kline.low,
+            kline.close,
+            kline.volume,
+            kline.close_time,
+            kline.quote_volume,
+            kline.trades,
+            kline.taker_buy_volume,
+            kline.taker_buy_quote_volume,
+            "0".into(),
+        )
+    }
+}
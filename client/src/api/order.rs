@@ -1,20 +1,37 @@
-use super::{types::*, utils, BinanceOkResponse, BinanceRequest, RequestPayload};
-use pyo3::pyclass;
+use super::{
+    decimal::{Price, Qty},
+    order_list::OrderListResponse,
+    signed_request,
+    types::*,
+    utils, BinanceOkResponse, BinanceRequest, RequestPayload,
+};
+use crate::Result;
+use pyo3::{pyclass, pymethods};
 use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, marker::PhantomData};
 
+/// Response types an order can be placed with
+pub trait OrderResponseType: for<'de> BinanceOkResponse<'de> {}
+
+/// ACK and RESULT responses
+impl OrderResponseType for OrderResponse {}
+
+impl OrderResponseType for OrderResponseFull {}
+
+/// `order.place` request, `R` is the response it is answered with, see `OrderRequest::full`
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct OrderRequest {
+pub struct OrderRequest<R = OrderResponse> {
     pub api_key: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub new_client_order_id: Option<u32>,
     pub new_order_resp_type: NewOrderRespType,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub price: Option<String>,
+    pub price: Option<Price>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub quantity: Option<String>,
+    pub quantity: Option<Qty>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub quote_order_qty: Option<String>,
+    pub quote_order_qty: Option<Qty>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub recv_window: Option<u32>,
     pub side: Side,
@@ -26,6 +43,11 @@ pub struct OrderRequest {
     pub timestamp: u64,
     #[serde(rename = "type")]
     pub order_type: OrderType,
+    /// Send as `order.test`, which is validated but never reaches the matching engine
+    #[serde(skip)]
+    pub test: bool,
+    #[serde(skip)]
+    pub response: PhantomData<R>,
 }
 
 impl OrderRequest {
@@ -34,9 +56,9 @@ impl OrderRequest {
         symbol: &str,
         side: Side,
         order_type: OrderType,
-        quantity: Option<String>,
+        quantity: Option<Qty>,
     ) -> Self {
-        let timestamp = utils::timestamp();
+        let timestamp = utils::server_timestamp();
 
         OrderRequest {
             symbol: symbol.into(),
@@ -52,17 +74,58 @@ impl OrderRequest {
             time_in_force: Default::default(),
             price: Default::default(),
             signature: Default::default(),
+            test: false,
+            response: PhantomData,
+        }
+    }
+
+    /// Ask for the FULL response, with the fills of the order
+    pub fn full(self) -> OrderRequest<OrderResponseFull> {
+        OrderRequest {
+            api_key: self.api_key,
+            new_client_order_id: self.new_client_order_id,
+            new_order_resp_type: NewOrderRespType::Full,
+            price: self.price,
+            quantity: self.quantity,
+            quote_order_qty: self.quote_order_qty,
+            recv_window: self.recv_window,
+            side: self.side,
+            signature: self.signature,
+            symbol: self.symbol,
+            time_in_force: self.time_in_force,
+            timestamp: self.timestamp,
+            order_type: self.order_type,
+            test: self.test,
+            response: PhantomData,
         }
     }
 }
 
-impl BinanceRequest for OrderRequest {
-    type Response = OrderResponse;
+impl<R: OrderResponseType> BinanceRequest for OrderRequest<R> {
+    type Response = R;
 
     const METHOD: &'static str = "order.place";
+
+    const ORDERS: u32 = 1;
+
+    fn method(&self) -> &'static str {
+        if self.test {
+            "order.test"
+        } else {
+            Self::METHOD
+        }
+    }
+
+    fn orders(&self) -> u32 {
+        if self.test {
+            0
+        } else {
+            Self::ORDERS
+        }
+    }
 }
 
-impl RequestPayload for OrderRequest {
+impl<R: OrderResponseType> RequestPayload for OrderRequest<R> {
     fn set_signature(&mut self, signature: String) {
         self.signature = Some(signature);
     }
@@ -71,27 +134,44 @@ impl RequestPayload for OrderRequest {
         self.signature.is_some()
     }
 
-    fn payload(&self) -> String {
-        serde_qs::to_string(&self).unwrap()
+    fn payload(&self) -> Result<String> {
+        Ok(serde_qs::to_string(&self)?)
     }
 }
 
+signed_request!(
+    OrderCancelRequest,
+    OrderStatusRequest,
+    OpenOrdersStatusRequest,
+    OpenOrdersCancelAllRequest,
+    OrderCancelReplaceRequest,
+);
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 #[pyclass]
 pub struct OrderResponse {
+    // `order.test` replies with an empty object
     #[pyo3(get)]
+    #[serde(default)]
     pub symbol: String,
     #[pyo3(get)]
+    #[serde(default)]
+    pub order_id: u64,
+    #[pyo3(get)]
+    #[serde(default)]
+    pub client_order_id: String,
+    #[pyo3(get)]
+    #[serde(default)]
     pub transact_time: u64,
     #[pyo3(get)]
-    pub price: Option<String>,
+    pub price: Option<Price>,
     #[pyo3(get)]
-    pub orig_qty: Option<String>,
+    pub orig_qty: Option<Qty>,
     #[pyo3(get)]
-    pub executed_qty: Option<String>,
+    pub executed_qty: Option<Qty>,
     #[pyo3(get)]
-    pub cummulative_quote_qty: Option<String>,
+    pub cummulative_quote_qty: Option<Qty>,
     #[pyo3(get)]
     pub status: Option<OrderStatus>,
 }
@@ -102,6 +182,8 @@ impl OrderResponse {
             symbol: symbol.into(),
             transact_time: transaction_time,
             status: Some(status),
+            order_id: Default::default(),
+            client_order_id: Default::default(),
             price: Default::default(),
             orig_qty: Default::default(),
             executed_qty: Default::default(),
@@ -111,3 +193,411 @@ impl OrderResponse {
 }
 
 impl<'de> BinanceOkResponse<'de> for OrderResponse {}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+#[pyclass]
+pub struct Fill {
+    #[pyo3(get)]
+    pub price: Price,
+    #[pyo3(get)]
+    pub qty: Qty,
+    #[pyo3(get)]
+    pub commission: Qty,
+    #[pyo3(get)]
+    pub commission_asset: String,
+    #[pyo3(get)]
+    pub trade_id: i64,
+}
+
+/// FULL response, `order.test` replies with an empty object so every field has a default
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+#[pyclass]
+pub struct OrderResponseFull {
+    #[pyo3(get)]
+    pub symbol: String,
+    #[pyo3(get)]
+    pub order_id: u64,
+    #[pyo3(get)]
+    pub order_list_id: i64,
+    #[pyo3(get)]
+    pub client_order_id: String,
+    #[pyo3(get)]
+    pub transact_time: u64,
+    #[pyo3(get)]
+    pub price: Price,
+    #[pyo3(get)]
+    pub orig_qty: Qty,
+    #[pyo3(get)]
+    pub executed_qty: Qty,
+    #[pyo3(get)]
+    pub cummulative_quote_qty: Qty,
+    #[pyo3(get)]
+    pub status: Option<OrderStatus>,
+    #[pyo3(get)]
+    pub time_in_force: Option<TimeInForce>,
+    #[pyo3(get)]
+    #[serde(rename = "type")]
+    pub order_type: Option<OrderType>,
+    #[pyo3(get)]
+    pub side: Option<Side>,
+    #[pyo3(get)]
+    pub fills: Vec<Fill>,
+}
+
+impl<'de> BinanceOkResponse<'de> for OrderResponseFull {}
+
+#[pymethods]
+impl OrderResponseFull {
+    /// Average price of the fills, weighted by quantity
+    pub fn avg_price(&self) -> Option<f64> {
+        let (mut notional, mut qty) = (0., 0.);
+        for fill in &self.fills {
+            notional += fill.price.to_f64() * fill.qty.to_f64();
+            qty += fill.qty.to_f64();
+        }
+        (qty > 0.).then(|| notional / qty)
+    }
+
+    /// Total commission paid per asset
+    pub fn commissions(&self) -> HashMap<String, f64> {
+        let mut commissions = HashMap::new();
+        for fill in &self.fills {
+            *commissions
+                .entry(fill.commission_asset.clone())
+                .or_default() += fill.commission.to_f64();
+        }
+        commissions
+    }
+}
+
+// Fields of signed requests are kept in alphabetical order of their wire names,
+// as Binance signs the sorted parameters
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderCancelRequest {
+    pub api_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel_restrictions: Option<CancelRestrictions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_client_order_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orig_client_order_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recv_window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub symbol: String,
+    pub timestamp: u64,
+}
+
+impl OrderCancelRequest {
+    /// Cancel by order id, set `orig_client_order_id` instead to cancel by client id
+    pub fn new(api_key: &str, symbol: &str, order_id: u64) -> Self {
+        OrderCancelRequest {
+            api_key: api_key.into(),
+            symbol: symbol.into(),
+            order_id: Some(order_id),
+            timestamp: utils::server_timestamp(),
+            cancel_restrictions: Default::default(),
+            new_client_order_id: Default::default(),
+            orig_client_order_id: Default::default(),
+            recv_window: Default::default(),
+            signature: Default::default(),
+        }
+    }
+}
+
+impl BinanceRequest for OrderCancelRequest {
+    type Response = CancelOrderResponse;
+
+    const METHOD: &'static str = "order.cancel";
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderStatusRequest {
+    pub api_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orig_client_order_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recv_window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub symbol: String,
+    pub timestamp: u64,
+}
+
+impl OrderStatusRequest {
+    /// Query by order id, set `orig_client_order_id` instead to query by client id
+    pub fn new(api_key: &str, symbol: &str, order_id: u64) -> Self {
+        OrderStatusRequest {
+            api_key: api_key.into(),
+            symbol: symbol.into(),
+            order_id: Some(order_id),
+            timestamp: utils::server_timestamp(),
+            orig_client_order_id: Default::default(),
+            recv_window: Default::default(),
+            signature: Default::default(),
+        }
+    }
+}
+
+impl BinanceRequest for OrderStatusRequest {
+    type Response = OrderStatusResponse;
+
+    const METHOD: &'static str = "order.status";
+
+    const WEIGHT: u32 = 4;
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenOrdersStatusRequest {
+    pub api_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recv_window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// All symbols when omitted, at a much higher weight
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    pub timestamp: u64,
+}
+
+impl OpenOrdersStatusRequest {
+    pub fn new(api_key: &str, symbol: Option<&str>) -> Self {
+        OpenOrdersStatusRequest {
+            api_key: api_key.into(),
+            symbol: symbol.map(Into::into),
+            timestamp: utils::server_timestamp(),
+            recv_window: Default::default(),
+            signature: Default::default(),
+        }
+    }
+}
+
+impl BinanceRequest for OpenOrdersStatusRequest {
+    type Response = Vec<OrderStatusResponse>;
+
+    const METHOD: &'static str = "openOrders.status";
+
+    // weight for a single symbol
+    const WEIGHT: u32 = 6;
+
+    fn weight(&self) -> u32 {
+        match self.symbol {
+            Some(_) => Self::WEIGHT,
+            None => 80,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenOrdersCancelAllRequest {
+    pub api_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recv_window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub symbol: String,
+    pub timestamp: u64,
+}
+
+impl OpenOrdersCancelAllRequest {
+    pub fn new(api_key: &str, symbol: &str) -> Self {
+        OpenOrdersCancelAllRequest {
+            api_key: api_key.into(),
+            symbol: symbol.into(),
+            timestamp: utils::server_timestamp(),
+            recv_window: Default::default(),
+            signature: Default::default(),
+        }
+    }
+}
+
+impl BinanceRequest for OpenOrdersCancelAllRequest {
+    type Response = Vec<CanceledOrder>;
+
+    const METHOD: &'static str = "openOrders.cancelAll";
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderCancelReplaceRequest {
+    pub api_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel_new_client_order_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel_order_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel_orig_client_order_id: Option<String>,
+    pub cancel_replace_mode: CancelReplaceMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_client_order_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_order_resp_type: Option<NewOrderRespType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<Price>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<Qty>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quote_order_qty: Option<Qty>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recv_window: Option<u32>,
+    pub side: Side,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub symbol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_in_force: Option<TimeInForce>,
+    pub timestamp: u64,
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+}
+
+impl OrderCancelReplaceRequest {
+    /// Cancel the order with the given id and place a new one in its stead
+    pub fn new(
+        api_key: &str,
+        symbol: &str,
+        cancel_order_id: u64,
+        side: Side,
+        order_type: OrderType,
+        quantity: Option<Qty>,
+    ) -> Self {
+        OrderCancelReplaceRequest {
+            api_key: api_key.into(),
+            symbol: symbol.into(),
+            cancel_order_id: Some(cancel_order_id),
+            cancel_replace_mode: CancelReplaceMode::StopOnFailure,
+            side,
+            order_type,
+            quantity,
+            timestamp: utils::server_timestamp(),
+            cancel_new_client_order_id: Default::default(),
+            cancel_orig_client_order_id: Default::default(),
+            new_client_order_id: Default::default(),
+            new_order_resp_type: Default::default(),
+            price: Default::default(),
+            quote_order_qty: Default::default(),
+            recv_window: Default::default(),
+            time_in_force: Default::default(),
+            signature: Default::default(),
+        }
+    }
+}
+
+impl BinanceRequest for OrderCancelReplaceRequest {
+    type Response = CancelReplaceResponse;
+
+    const METHOD: &'static str = "order.cancelReplace";
+
+    const ORDERS: u32 = 1;
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+#[pyclass]
+pub struct CancelOrderResponse {
+    #[pyo3(get)]
+    pub symbol: String,
+    #[pyo3(get)]
+    pub orig_client_order_id: String,
+    #[pyo3(get)]
+    pub order_id: u64,
+    #[pyo3(get)]
+    pub order_list_id: i64,
+    #[pyo3(get)]
+    pub client_order_id: String,
+    #[pyo3(get)]
+    pub transact_time: Option<u64>,
+    #[pyo3(get)]
+    pub price: Price,
+    #[pyo3(get)]
+    pub orig_qty: Qty,
+    #[pyo3(get)]
+    pub executed_qty: Qty,
+    #[pyo3(get)]
+    pub cummulative_quote_qty: Qty,
+    #[pyo3(get)]
+    pub status: OrderStatus,
+    #[pyo3(get)]
+    pub time_in_force: TimeInForce,
+    #[pyo3(get)]
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    #[pyo3(get)]
+    pub side: Side,
+}
+
+impl<'de> BinanceOkResponse<'de> for CancelOrderResponse {}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+#[pyclass]
+pub struct OrderStatusResponse {
+    #[pyo3(get)]
+    pub symbol: String,
+    #[pyo3(get)]
+    pub order_id: u64,
+    #[pyo3(get)]
+    pub order_list_id: i64,
+    #[pyo3(get)]
+    pub client_order_id: String,
+    #[pyo3(get)]
+    pub price: Price,
+    #[pyo3(get)]
+    pub orig_qty: Qty,
+    #[pyo3(get)]
+    pub executed_qty: Qty,
+    #[pyo3(get)]
+    pub cummulative_quote_qty: Qty,
+    #[pyo3(get)]
+    pub status: OrderStatus,
+    #[pyo3(get)]
+    pub time_in_force: TimeInForce,
+    #[pyo3(get)]
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    #[pyo3(get)]
+    pub side: Side,
+    #[pyo3(get)]
+    pub stop_price: Option<Price>,
+    #[pyo3(get)]
+    pub time: u64,
+    #[pyo3(get)]
+    pub update_time: u64,
+    #[pyo3(get)]
+    pub is_working: bool,
+}
+
+impl<'de> BinanceOkResponse<'de> for OrderStatusResponse {}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelReplaceResponse {
+    pub cancel_result: CancelReplaceResult,
+    pub new_order_result: CancelReplaceResult,
+    pub cancel_response: Option<CancelOrderResponse>,
+    pub new_order_response: Option<OrderResponse>,
+}
+
+impl<'de> BinanceOkResponse<'de> for CancelReplaceResponse {}
+
+/// Entry of `openOrders.cancelAll`, which cancels order lists as a whole
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum CanceledOrder {
+    List(OrderListResponse),
+    Order(CancelOrderResponse),
+}
+
+impl<'de> BinanceOkResponse<'de> for CanceledOrder {}
@@ -0,0 +1,285 @@
+use super::{
+    decimal::{Price, Qty},
+    signed_request,
+    types::*,
+    utils, BinanceOkResponse, BinanceRequest,
+};
+use pyo3::pyclass;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderListPlaceOcoRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub above_client_order_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub above_price: Option<Price>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub above_stop_price: Option<Price>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub above_time_in_force: Option<TimeInForce>,
+    pub above_type: OrderType,
+    pub api_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub below_client_order_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub below_price: Option<Price>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub below_stop_price: Option<Price>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub below_time_in_force: Option<TimeInForce>,
+    pub below_type: OrderType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list_client_order_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_order_resp_type: Option<NewOrderRespType>,
+    pub quantity: Qty,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recv_window: Option<u32>,
+    pub side: Side,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub symbol: String,
+    pub timestamp: u64,
+}
+
+impl OrderListPlaceOcoRequest {
+    pub fn new(
+        api_key: &str,
+        symbol: &str,
+        side: Side,
+        quantity: Qty,
+        above_type: OrderType,
+        below_type: OrderType,
+    ) -> Self {
+        OrderListPlaceOcoRequest {
+            api_key: api_key.into(),
+            symbol: symbol.into(),
+            side,
+            quantity,
+            above_type,
+            below_type,
+            timestamp: utils::server_timestamp(),
+            above_client_order_id: Default::default(),
+            above_price: Default::default(),
+            above_stop_price: Default::default(),
+            above_time_in_force: Default::default(),
+            below_client_order_id: Default::default(),
+            below_price: Default::default(),
+            below_stop_price: Default::default(),
+            below_time_in_force: Default::default(),
+            list_client_order_id: Default::default(),
+            new_order_resp_type: Default::default(),
+            recv_window: Default::default(),
+            signature: Default::default(),
+        }
+    }
+
+    /// Take profit at `take_profit` with a LIMIT_MAKER leg and stop out at `stop_price`
+    /// with a STOP_LOSS_LIMIT leg, limited at `stop_limit_price` or the stop price itself.
+    /// Selling puts the take profit above the market, buying below it.
+    pub fn bracket(
+        api_key: &str,
+        symbol: &str,
+        side: Side,
+        quantity: Qty,
+        take_profit: Price,
+        stop_price: Price,
+        stop_limit_price: Option<Price>,
+    ) -> Self {
+        let (above_type, below_type) = match side {
+            Side::Sell => (OrderType::LimitMaker, OrderType::StopLossLimit),
+            Side::Buy => (OrderType::StopLossLimit, OrderType::LimitMaker),
+        };
+        let mut request = Self::new(api_key, symbol, side, quantity, above_type, below_type);
+        let stop_limit_price = stop_limit_price.unwrap_or(stop_price);
+        match side {
+            Side::Sell => {
+                request.above_price = Some(take_profit);
+                request.below_price = Some(stop_limit_price);
+                request.below_stop_price = Some(stop_price);
+                request.below_time_in_force = Some(TimeInForce::Gtc);
+            }
+            Side::Buy => {
+                request.below_price = Some(take_profit);
+                request.above_price = Some(stop_limit_price);
+                request.above_stop_price = Some(stop_price);
+                request.above_time_in_force = Some(TimeInForce::Gtc);
+            }
+        }
+        request
+    }
+}
+
+impl BinanceRequest for OrderListPlaceOcoRequest {
+    type Response = OrderListResponse;
+
+    const METHOD: &'static str = "orderList.place.oco";
+
+    const ORDERS: u32 = 2;
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderListCancelRequest {
+    pub api_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list_client_order_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_client_order_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_list_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recv_window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub symbol: String,
+    pub timestamp: u64,
+}
+
+impl OrderListCancelRequest {
+    /// Cancel by list id, set `list_client_order_id` instead to cancel by client id
+    pub fn new(api_key: &str, symbol: &str, order_list_id: i64) -> Self {
+        OrderListCancelRequest {
+            api_key: api_key.into(),
+            symbol: symbol.into(),
+            order_list_id: Some(order_list_id),
+            timestamp: utils::server_timestamp(),
+            list_client_order_id: Default::default(),
+            new_client_order_id: Default::default(),
+            recv_window: Default::default(),
+            signature: Default::default(),
+        }
+    }
+}
+
+impl BinanceRequest for OrderListCancelRequest {
+    type Response = OrderListResponse;
+
+    const METHOD: &'static str = "orderList.cancel";
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderListStatusRequest {
+    pub api_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_list_id: Option<i64>,
+    /// Client id of the list
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orig_client_order_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recv_window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub timestamp: u64,
+}
+
+impl OrderListStatusRequest {
+    pub fn new(api_key: &str, order_list_id: i64) -> Self {
+        OrderListStatusRequest {
+            api_key: api_key.into(),
+            order_list_id: Some(order_list_id),
+            timestamp: utils::server_timestamp(),
+            orig_client_order_id: Default::default(),
+            recv_window: Default::default(),
+            signature: Default::default(),
+        }
+    }
+}
+
+impl BinanceRequest for OrderListStatusRequest {
+    type Response = OrderListResponse;
+
+    const METHOD: &'static str = "orderList.status";
+
+    const WEIGHT: u32 = 4;
+}
+
+signed_request!(
+    OrderListPlaceOcoRequest,
+    OrderListCancelRequest,
+    OrderListStatusRequest,
+);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+#[pyclass]
+pub struct OrderListEntry {
+    // the user data stream uses short names
+    #[pyo3(get)]
+    #[serde(alias = "s")]
+    pub symbol: String,
+    #[pyo3(get)]
+    #[serde(alias = "i")]
+    pub order_id: u64,
+    #[pyo3(get)]
+    #[serde(alias = "c")]
+    pub client_order_id: String,
+}
+
+/// State of a child order as reported when placing or canceling its list
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+#[pyclass]
+pub struct OrderReport {
+    #[pyo3(get)]
+    pub symbol: String,
+    #[pyo3(get)]
+    pub order_id: u64,
+    #[pyo3(get)]
+    pub order_list_id: i64,
+    #[pyo3(get)]
+    pub client_order_id: String,
+    #[pyo3(get)]
+    pub orig_client_order_id: Option<String>,
+    #[pyo3(get)]
+    pub transact_time: Option<u64>,
+    #[pyo3(get)]
+    pub price: Price,
+    #[pyo3(get)]
+    pub orig_qty: Qty,
+    #[pyo3(get)]
+    pub executed_qty: Qty,
+    #[pyo3(get)]
+    pub cummulative_quote_qty: Qty,
+    #[pyo3(get)]
+    pub status: OrderStatus,
+    #[pyo3(get)]
+    pub time_in_force: TimeInForce,
+    #[pyo3(get)]
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    #[pyo3(get)]
+    pub side: Side,
+    #[pyo3(get)]
+    pub stop_price: Option<Price>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+#[pyclass]
+pub struct OrderListResponse {
+    #[pyo3(get)]
+    pub order_list_id: i64,
+    #[pyo3(get)]
+    pub contingency_type: ContingencyType,
+    #[pyo3(get)]
+    pub list_status_type: ListStatusType,
+    #[pyo3(get)]
+    pub list_order_status: ListOrderStatus,
+    #[pyo3(get)]
+    pub list_client_order_id: String,
+    #[pyo3(get)]
+    pub transaction_time: u64,
+    #[pyo3(get)]
+    pub symbol: String,
+    #[pyo3(get)]
+    pub orders: Vec<OrderListEntry>,
+    /// Only filled in by `orderList.place.oco` and `orderList.cancel`
+    #[pyo3(get)]
+    #[serde(default)]
+    pub order_reports: Vec<OrderReport>,
+}
+
+impl<'de> BinanceOkResponse<'de> for OrderListResponse {}
@@ -1,4 +1,5 @@
 use super::{utils, RequestPayload};
+use crate::Result;
 use serde::{Deserialize, Serialize};
 use std::ops::{Deref, DerefMut};
 
@@ -11,10 +12,12 @@ impl SecretKey {
         SecretKey(key.into())
     }
 
-    pub fn sign<T: RequestPayload>(&self, request: &mut T) {
-        let payload = request.payload();
+    pub fn sign<T: RequestPayload>(&self, request: &mut T) -> Result<()> {
+        let payload = request.payload()?;
         let signature = utils::sign_payload(&payload, self.as_bytes());
         request.set_signature(signature);
+
+        Ok(())
     }
 }
 
@@ -60,17 +63,17 @@ mod tests {
             "BTCUSDT",
             Side::Sell,
             OrderType::Limit,
-            Some("0.01000000".into()),
+            Some("0.01000000".parse().unwrap()),
         );
 
         order_request.new_order_resp_type = NewOrderRespType::Ack;
-        order_request.price = Some("52000.00".into());
+        order_request.price = Some("52000.00".parse().unwrap());
         order_request.recv_window = Some(100);
         order_request.time_in_force = Some(TimeInForce::Gtc);
         order_request.timestamp = 1645423376532;
 
         assert_eq!(order_request.signature, None);
-        secret_key.sign(&mut order_request);
+        secret_key.sign(&mut order_request).unwrap();
         assert_eq!(
             order_request.signature.unwrap(),
             "cc15477742bd704c29492d96c7ead9414dfd8e0ec4a00f947bb5bb454ddbd08a"
@@ -1,6 +1,28 @@
 use serde::{Deserialize, Serialize};
 
-use crate::api::{BinanceOkResponse, BinanceRequest};
+use crate::{
+    api::{
+        decimal::{Price, Qty},
+        types::KlineInterval,
+        ws::{RequestId, StreamResponse, WsResponse},
+        BinanceOkResponse, BinanceRequest,
+    },
+    Result,
+};
+
+/// Stream requests take integer ids and are acked as a `StreamResponse`
+macro_rules! stream_request {
+    () => {
+        fn request_id() -> RequestId {
+            RequestId::next_number()
+        }
+
+        fn decode(bytes: &[u8]) -> Result<WsResponse<Self::Response>> {
+            let ack = serde_json::from_slice::<StreamResponse<Self::Response>>(bytes)?;
+            Ok(ack.into_ws_response())
+        }
+    };
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -19,14 +41,85 @@ impl BinanceRequest for SubscribeRequest {
     type Response = SubscribeResponse;
 
     const METHOD: &'static str = "SUBSCRIBE";
+
+    // stream connections are limited by messages per second instead
+    const WEIGHT: u32 = 0;
+
+    stream_request!();
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Acked with a null result
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct SubscribeResponse;
 
 impl<'de> BinanceOkResponse<'de> for SubscribeResponse {}
 
-// TODO: prices and qtys as floats with parsing to/from string
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct UnsubscribeRequest {
+    pub symbols: Vec<String>,
+}
+
+impl UnsubscribeRequest {
+    pub fn new(symbols: Vec<String>) -> Self {
+        Self { symbols }
+    }
+}
+
+impl BinanceRequest for UnsubscribeRequest {
+    type Response = UnsubscribeResponse;
+
+    const METHOD: &'static str = "UNSUBSCRIBE";
+
+    const WEIGHT: u32 = 0;
+
+    stream_request!();
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct UnsubscribeResponse;
+
+impl<'de> BinanceOkResponse<'de> for UnsubscribeResponse {}
+
+/// Streams the connection is subscribed to
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ListSubscriptionsRequest {
+    // sent as an empty array
+    params: Vec<String>,
+}
+
+impl ListSubscriptionsRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl BinanceRequest for ListSubscriptionsRequest {
+    type Response = ListSubscriptionsResponse;
+
+    const METHOD: &'static str = "LIST_SUBSCRIPTIONS";
+
+    const WEIGHT: u32 = 0;
+
+    stream_request!();
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ListSubscriptionsResponse {
+    pub streams: Vec<String>,
+}
+
+impl<'de> BinanceOkResponse<'de> for ListSubscriptionsResponse {}
+
+/// Frame of a combined stream connection, the payload tagged with the stream it
+/// belongs to, e.g. `{"stream":"btcusdt@bookTicker","data":{...}}`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StreamEnvelope<T> {
+    pub stream: String,
+    pub data: T,
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SubscriptionUpdate {
@@ -35,11 +128,105 @@ pub struct SubscriptionUpdate {
     #[serde(rename = "s")]
     pub symbol: String,
     #[serde(rename = "b")]
-    pub best_bid_price: String,
+    pub best_bid_price: Price,
     #[serde(rename = "B")]
-    pub best_bid_qty: String,
+    pub best_bid_qty: Qty,
     #[serde(rename = "a")]
-    pub best_ask_price: String,
+    pub best_ask_price: Price,
     #[serde(rename = "A")]
-    pub best_ask_qty: String,
+    pub best_ask_qty: Qty,
+}
+
+/// Payload of the `<symbol>@trade` stream
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Trade {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "t")]
+    pub trade_id: u64,
+    #[serde(rename = "p")]
+    pub price: Price,
+    #[serde(rename = "q")]
+    pub quantity: Qty,
+    #[serde(rename = "T")]
+    pub trade_time: u64,
+    /// The seller was the aggressor
+    #[serde(rename = "m")]
+    pub is_buyer_maker: bool,
+}
+
+/// Payload of the `<symbol>@aggTrade` stream, trades of a single taker order at the
+/// same price aggregated together
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AggTrade {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "a")]
+    pub agg_trade_id: u64,
+    #[serde(rename = "p")]
+    pub price: Price,
+    #[serde(rename = "q")]
+    pub quantity: Qty,
+    #[serde(rename = "f")]
+    pub first_trade_id: u64,
+    #[serde(rename = "l")]
+    pub last_trade_id: u64,
+    #[serde(rename = "T")]
+    pub trade_time: u64,
+    /// The seller was the aggressor
+    #[serde(rename = "m")]
+    pub is_buyer_maker: bool,
+}
+
+/// Payload of the `<symbol>@kline_<interval>` stream, pushed as the current candle
+/// changes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KlineUpdate {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "k")]
+    pub kline: StreamKline,
+}
+
+impl KlineUpdate {
+    pub fn stream(symbol: &str, interval: KlineInterval) -> String {
+        format!("{}@kline_{}", symbol.to_lowercase(), interval.as_str())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StreamKline {
+    #[serde(rename = "t")]
+    pub open_time: u64,
+    #[serde(rename = "T")]
+    pub close_time: u64,
+    #[serde(rename = "i")]
+    pub interval: KlineInterval,
+    #[serde(rename = "f")]
+    pub first_trade_id: i64,
+    #[serde(rename = "L")]
+    pub last_trade_id: i64,
+    #[serde(rename = "o")]
+    pub open: Price,
+    #[serde(rename = "c")]
+    pub close: Price,
+    #[serde(rename = "h")]
+    pub high: Price,
+    #[serde(rename = "l")]
+    pub low: Price,
+    #[serde(rename = "v")]
+    pub volume: Qty,
+    #[serde(rename = "n")]
+    pub trades: u64,
+    /// The candle is complete, no further update will change it
+    #[serde(rename = "x")]
+    pub is_closed: bool,
+    #[serde(rename = "q")]
+    pub quote_volume: Qty,
 }
@@ -1,4 +1,4 @@
-use pyo3::{types::PyString, IntoPy, PyObject, Python, ToPyObject};
+use pyo3::{pyclass, types::PyString, IntoPy, PyObject, Python, ToPyObject};
 use serde::{Deserialize, Serialize};
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
@@ -36,6 +36,51 @@ pub enum TimeInForce {
     Fok,
 }
 
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CancelRestrictions {
+    OnlyNew,
+    OnlyPartiallyFilled,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CancelReplaceMode {
+    StopOnFailure,
+    AllowFailure,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CancelReplaceResult {
+    Success,
+    Failure,
+    NotAttempted,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ContingencyType {
+    Oco,
+    Oto,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ListStatusType {
+    Response,
+    ExecStarted,
+    AllDone,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ListOrderStatus {
+    Executing,
+    AllDone,
+    Reject,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum OrderStatus {
@@ -46,6 +91,19 @@ pub enum OrderStatus {
     PendingCancel,
     Rejected,
     Expired,
+    ExpiredInMatch,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ExecutionType {
+    New,
+    Canceled,
+    Replaced,
+    Rejected,
+    Trade,
+    Expired,
+    TradePrevention,
 }
 
 impl IntoPy<PyObject> for OrderStatus {
@@ -53,3 +111,226 @@ impl IntoPy<PyObject> for OrderStatus {
         PyString::new(py, &format!("{self:?}")).to_object(py)
     }
 }
+
+impl IntoPy<PyObject> for ExecutionType {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyString::new(py, &format!("{self:?}")).to_object(py)
+    }
+}
+
+impl IntoPy<PyObject> for Side {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyString::new(py, &format!("{self:?}")).to_object(py)
+    }
+}
+
+impl IntoPy<PyObject> for OrderType {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyString::new(py, &format!("{self:?}")).to_object(py)
+    }
+}
+
+impl IntoPy<PyObject> for ContingencyType {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyString::new(py, &format!("{self:?}")).to_object(py)
+    }
+}
+
+impl IntoPy<PyObject> for ListStatusType {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyString::new(py, &format!("{self:?}")).to_object(py)
+    }
+}
+
+impl IntoPy<PyObject> for ListOrderStatus {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyString::new(py, &format!("{self:?}")).to_object(py)
+    }
+}
+
+impl IntoPy<PyObject> for TimeInForce {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyString::new(py, &format!("{self:?}")).to_object(py)
+    }
+}
+
+/// Candle length, in the notation of the `klines` requests and `kline_<interval>` streams
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KlineInterval {
+    #[serde(rename = "1s")]
+    S1,
+    #[serde(rename = "1m")]
+    M1,
+    #[serde(rename = "3m")]
+    M3,
+    #[serde(rename = "5m")]
+    M5,
+    #[serde(rename = "15m")]
+    M15,
+    #[serde(rename = "30m")]
+    M30,
+    #[serde(rename = "1h")]
+    H1,
+    #[serde(rename = "2h")]
+    H2,
+    #[serde(rename = "4h")]
+    H4,
+    #[serde(rename = "6h")]
+    H6,
+    #[serde(rename = "8h")]
+    H8,
+    #[serde(rename = "12h")]
+    H12,
+    #[serde(rename = "1d")]
+    D1,
+    #[serde(rename = "3d")]
+    D3,
+    #[serde(rename = "1w")]
+    W1,
+    #[serde(rename = "1M")]
+    Mo1,
+}
+
+impl KlineInterval {
+    pub const ALL: [KlineInterval; 16] = [
+        KlineInterval::S1,
+        KlineInterval::M1,
+        KlineInterval::M3,
+        KlineInterval::M5,
+        KlineInterval::M15,
+        KlineInterval::M30,
+        KlineInterval::H1,
+        KlineInterval::H2,
+        KlineInterval::H4,
+        KlineInterval::H6,
+        KlineInterval::H8,
+        KlineInterval::H12,
+        KlineInterval::D1,
+        KlineInterval::D3,
+        KlineInterval::W1,
+        KlineInterval::Mo1,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KlineInterval::S1 => "1s",
+            KlineInterval::M1 => "1m",
+            KlineInterval::M3 => "3m",
+            KlineInterval::M5 => "5m",
+            KlineInterval::M15 => "15m",
+            KlineInterval::M30 => "30m",
+            KlineInterval::H1 => "1h",
+            KlineInterval::H2 => "2h",
+            KlineInterval::H4 => "4h",
+            KlineInterval::H6 => "6h",
+            KlineInterval::H8 => "8h",
+            KlineInterval::H12 => "12h",
+            KlineInterval::D1 => "1d",
+            KlineInterval::D3 => "3d",
+            KlineInterval::W1 => "1w",
+            KlineInterval::Mo1 => "1M",
+        }
+    }
+
+    pub fn parse(interval: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|candidate| candidate.as_str() == interval)
+    }
+
+    /// Length in milliseconds, None for months which vary
+    pub fn millis(&self) -> Option<u64> {
+        const MINUTE: u64 = 60_000;
+        const HOUR: u64 = 60 * MINUTE;
+        const DAY: u64 = 24 * HOUR;
+        Some(match self {
+            KlineInterval::S1 => 1_000,
+            KlineInterval::M1 => MINUTE,
+            KlineInterval::M3 => 3 * MINUTE,
+            KlineInterval::M5 => 5 * MINUTE,
+            KlineInterval::M15 => 15 * MINUTE,
+            KlineInterval::M30 => 30 * MINUTE,
+            KlineInterval::H1 => HOUR,
+            KlineInterval::H2 => 2 * HOUR,
+            KlineInterval::H4 => 4 * HOUR,
+            KlineInterval::H6 => 6 * HOUR,
+            KlineInterval::H8 => 8 * HOUR,
+            KlineInterval::H12 => 12 * HOUR,
+            KlineInterval::D1 => DAY,
+            KlineInterval::D3 => 3 * DAY,
+            KlineInterval::W1 => 7 * DAY,
+            KlineInterval::Mo1 => return None,
+        })
+    }
+}
+
+impl IntoPy<PyObject> for KlineInterval {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyString::new(py, self.as_str()).to_object(py)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RateLimitType {
+    RequestWeight,
+    Orders,
+    RawRequests,
+}
+
+impl IntoPy<PyObject> for RateLimitType {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyString::new(py, &format!("{self:?}")).to_object(py)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RateLimitInterval {
+    Second,
+    Minute,
+    Hour,
+    Day,
+}
+
+impl RateLimitInterval {
+    pub fn millis(&self) -> u64 {
+        match self {
+            RateLimitInterval::Second => 1_000,
+            RateLimitInterval::Minute => 60_000,
+            RateLimitInterval::Hour => 3_600_000,
+            RateLimitInterval::Day => 86_400_000,
+        }
+    }
+}
+
+impl IntoPy<PyObject> for RateLimitInterval {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyString::new(py, &format!("{self:?}")).to_object(py)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[pyclass]
+pub struct RateLimit {
+    #[pyo3(get)]
+    pub rate_limit_type: RateLimitType,
+    #[pyo3(get)]
+    pub interval: RateLimitInterval,
+    #[pyo3(get)]
+    pub interval_num: u32,
+    #[pyo3(get)]
+    pub limit: u32,
+    /// Current usage, only present in responses
+    #[pyo3(get)]
+    #[serde(default)]
+    pub count: u32,
+}
+
+impl RateLimit {
+    /// Length of the window in milliseconds
+    pub fn window(&self) -> u64 {
+        self.interval.millis() * self.interval_num as u64
+    }
+}
@@ -0,0 +1,240 @@
+use super::{
+    account::Balance,
+    decimal::{Price, Qty},
+    general::EmptyResponse,
+    order_list::OrderListEntry,
+    types::*,
+    BinanceOkResponse, BinanceRequest,
+};
+use pyo3::pyclass;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserDataStreamStartRequest {
+    pub api_key: String,
+}
+
+impl UserDataStreamStartRequest {
+    pub fn new(api_key: &str) -> Self {
+        UserDataStreamStartRequest {
+            api_key: api_key.into(),
+        }
+    }
+}
+
+impl BinanceRequest for UserDataStreamStartRequest {
+    type Response = ListenKeyResponse;
+
+    const METHOD: &'static str = "userDataStream.start";
+
+    const WEIGHT: u32 = 2;
+}
+
+/// Extend the validity of a listen key by 60 minutes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserDataStreamPingRequest {
+    pub api_key: String,
+    pub listen_key: String,
+}
+
+impl UserDataStreamPingRequest {
+    pub fn new(api_key: &str, listen_key: &str) -> Self {
+        UserDataStreamPingRequest {
+            api_key: api_key.into(),
+            listen_key: listen_key.into(),
+        }
+    }
+}
+
+impl BinanceRequest for UserDataStreamPingRequest {
+    type Response = EmptyResponse;
+
+    const METHOD: &'static str = "userDataStream.ping";
+
+    const WEIGHT: u32 = 2;
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserDataStreamStopRequest {
+    pub api_key: String,
+    pub listen_key: String,
+}
+
+impl UserDataStreamStopRequest {
+    pub fn new(api_key: &str, listen_key: &str) -> Self {
+        UserDataStreamStopRequest {
+            api_key: api_key.into(),
+            listen_key: listen_key.into(),
+        }
+    }
+}
+
+impl BinanceRequest for UserDataStreamStopRequest {
+    type Response = EmptyResponse;
+
+    const METHOD: &'static str = "userDataStream.stop";
+
+    const WEIGHT: u32 = 2;
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListenKeyResponse {
+    pub listen_key: String,
+}
+
+impl<'de> BinanceOkResponse<'de> for ListenKeyResponse {}
+
+/// Event of the user data stream, see
+/// https://binance-docs.github.io/apidocs/spot/en/#user-data-streams
+// execution reports are by far the most frequent events, boxing them buys nothing
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "e", rename_all = "camelCase")]
+pub enum UserDataEvent {
+    ExecutionReport(ExecutionReport),
+    OutboundAccountPosition(AccountPosition),
+    BalanceUpdate(BalanceUpdate),
+    ListStatus(ListStatus),
+    ListenKeyExpired(ListenKeyExpired),
+}
+
+/// Update of an order, sent for every state change and every trade
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[pyclass]
+pub struct ExecutionReport {
+    #[pyo3(get)]
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[pyo3(get)]
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[pyo3(get)]
+    #[serde(rename = "c")]
+    pub client_order_id: String,
+    #[pyo3(get)]
+    #[serde(rename = "S")]
+    pub side: Side,
+    #[pyo3(get)]
+    #[serde(rename = "o")]
+    pub order_type: OrderType,
+    #[pyo3(get)]
+    #[serde(rename = "f")]
+    pub time_in_force: TimeInForce,
+    #[pyo3(get)]
+    #[serde(rename = "q")]
+    pub quantity: Qty,
+    #[pyo3(get)]
+    #[serde(rename = "p")]
+    pub price: Price,
+    #[pyo3(get)]
+    #[serde(rename = "P")]
+    pub stop_price: Price,
+    #[pyo3(get)]
+    #[serde(rename = "g")]
+    pub order_list_id: i64,
+    /// Id of the canceled order for cancels, empty otherwise
+    #[pyo3(get)]
+    #[serde(rename = "C")]
+    pub orig_client_order_id: String,
+    #[pyo3(get)]
+    #[serde(rename = "x")]
+    pub execution_type: ExecutionType,
+    #[pyo3(get)]
+    #[serde(rename = "X")]
+    pub order_status: OrderStatus,
+    #[pyo3(get)]
+    #[serde(rename = "r")]
+    pub reject_reason: String,
+    #[pyo3(get)]
+    #[serde(rename = "i")]
+    pub order_id: u64,
+    #[pyo3(get)]
+    #[serde(rename = "l")]
+    pub last_executed_qty: Qty,
+    #[pyo3(get)]
+    #[serde(rename = "z")]
+    pub cumulative_filled_qty: Qty,
+    #[pyo3(get)]
+    #[serde(rename = "L")]
+    pub last_executed_price: Price,
+    #[pyo3(get)]
+    #[serde(rename = "n")]
+    pub commission: Qty,
+    #[pyo3(get)]
+    #[serde(rename = "N")]
+    pub commission_asset: Option<String>,
+    #[pyo3(get)]
+    #[serde(rename = "T")]
+    pub transaction_time: u64,
+    /// -1 unless the report is for a trade
+    #[pyo3(get)]
+    #[serde(rename = "t")]
+    pub trade_id: i64,
+    #[pyo3(get)]
+    #[serde(rename = "m")]
+    pub is_maker: bool,
+    #[pyo3(get)]
+    #[serde(rename = "Z")]
+    pub cumulative_quote_qty: Qty,
+}
+
+/// Balances that changed, sent after every balance change
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountPosition {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "u")]
+    pub last_update_time: u64,
+    #[serde(rename = "B")]
+    pub balances: Vec<Balance>,
+}
+
+/// Deposit, withdrawal or transfer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BalanceUpdate {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "a")]
+    pub asset: String,
+    #[serde(rename = "d")]
+    pub delta: Qty,
+    #[serde(rename = "T")]
+    pub clear_time: u64,
+}
+
+/// Update of an order list, sent along the reports of its orders
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ListStatus {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "g")]
+    pub order_list_id: i64,
+    #[serde(rename = "c")]
+    pub contingency_type: ContingencyType,
+    #[serde(rename = "l")]
+    pub list_status_type: ListStatusType,
+    #[serde(rename = "L")]
+    pub list_order_status: ListOrderStatus,
+    #[serde(rename = "r")]
+    pub list_reject_reason: String,
+    #[serde(rename = "C")]
+    pub list_client_order_id: String,
+    #[serde(rename = "T")]
+    pub transaction_time: u64,
+    #[serde(rename = "O")]
+    pub orders: Vec<OrderListEntry>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListenKeyExpired {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    pub listen_key: String,
+}
@@ -1,9 +1,16 @@
 use hmac::{Hmac, Mac, NewMac};
 use sha2::Sha256;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    sync::atomic::{AtomicI64, AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// estimated server clock minus local clock, and the round trip it was measured with
+static CLOCK_OFFSET: AtomicI64 = AtomicI64::new(0);
+static CLOCK_RTT: AtomicU64 = AtomicU64::new(0);
 
 pub fn sign_payload(query: &str, secret_key: &[u8]) -> String {
-    let mut hmac = Hmac::<Sha256>::new_from_slice(secret_key).unwrap();
+    let mut hmac = Hmac::<Sha256>::new_from_slice(secret_key).expect("HMAC takes keys of any size");
     hmac.update(query.as_bytes());
     let digest = hmac.finalize().into_bytes();
 
@@ -16,3 +23,23 @@ pub fn timestamp() -> u64 {
         .unwrap()
         .as_millis() as u64
 }
+
+/// Local timestamp corrected by the offset measured against the server clock
+pub fn server_timestamp() -> u64 {
+    timestamp().saturating_add_signed(clock_offset())
+}
+
+/// Offset of the server clock in milliseconds
+pub fn clock_offset() -> i64 {
+    CLOCK_OFFSET.load(Ordering::Relaxed)
+}
+
+/// Round trip time of the last clock measurement in milliseconds
+pub fn clock_rtt() -> u64 {
+    CLOCK_RTT.load(Ordering::Relaxed)
+}
+
+pub fn set_clock(offset: i64, rtt: u64) {
+    CLOCK_OFFSET.store(offset, Ordering::Relaxed);
+    CLOCK_RTT.store(rtt, Ordering::Relaxed);
+}
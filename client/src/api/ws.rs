@@ -1,11 +1,57 @@
-use super::BinanceRequest;
+use super::{error_code::BinanceErrorCode, types::RateLimit, BinanceRequest};
 use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+};
 use uuid::Uuid;
 
+/// Id of a request, the WS API accepts any string while market stream requests
+/// only take unsigned integers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RequestId {
+    Number(u64),
+    Uuid(Uuid),
+}
+
+impl RequestId {
+    pub fn new_uuid() -> Self {
+        RequestId::Uuid(Uuid::new_v4())
+    }
+
+    /// Next id of the process wide counter used for stream requests
+    pub fn next_number() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        RequestId::Number(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl From<Uuid> for RequestId {
+    fn from(id: Uuid) -> Self {
+        RequestId::Uuid(id)
+    }
+}
+
+impl From<u64> for RequestId {
+    fn from(id: u64) -> Self {
+        RequestId::Number(id)
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestId::Number(id) => id.fmt(f),
+            RequestId::Uuid(id) => id.fmt(f),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WsRequest<T> {
-    pub id: Uuid,
+    pub id: RequestId,
     pub method: String,
     pub params: T,
 }
@@ -14,8 +60,8 @@ pub struct WsRequest<T> {
 impl<T: BinanceRequest> WsRequest<T> {
     pub fn new(request: T) -> Self {
         WsRequest {
-            id: Uuid::new_v4(),
-            method: T::METHOD.into(),
+            id: T::request_id(),
+            method: request.method().into(),
             params: request,
         }
     }
@@ -24,25 +70,167 @@ impl<T: BinanceRequest> WsRequest<T> {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WsError {
-    pub code: i32,
+    pub code: BinanceErrorCode,
     pub msg: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<WsErrorData>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsErrorData {
+    pub server_time: Option<u64>,
+    /// Timestamp until which the client is banned, sent along 418 and 429 statuses
+    pub retry_after: Option<u64>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WsResponseHeader {
-    pub id: Uuid,
+    pub id: RequestId,
+    // missing from stream acks
+    #[serde(default)]
     pub status: u64,
     pub error: Option<WsError>,
+    #[serde(default)]
+    pub rate_limits: Vec<RateLimit>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-// A Binance failed response contains no result field
-//We make result generic, or can wrap it in an Option, so that we can use it for both successful and failed responses
+// A Binance failed response contains no result field, so it is wrapped in an Option
+// to use the same type for both successful and failed responses
 pub struct WsResponse<T> {
-    pub id: Uuid,
+    pub id: RequestId,
     pub status: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<WsError>,
-    pub result: T,
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub result: Option<T>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rate_limits: Vec<RateLimit>,
+}
+
+/// Ack of a market stream request, `{"result":null,"id":1}` on success and
+/// `{"error":{"code":2,"msg":"..."},"id":1}` on failure
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StreamResponse<T> {
+    pub id: RequestId,
+    // null for requests that only report success
+    #[serde(default = "Option::default")]
+    pub result: Option<T>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<StreamError>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StreamError {
+    pub code: i32,
+    pub msg: String,
+}
+
+impl<T: Default> StreamResponse<T> {
+    pub fn ok(id: RequestId, result: Option<T>) -> Self {
+        StreamResponse {
+            id,
+            result,
+            error: None,
+        }
+    }
+
+    pub fn err(id: RequestId, code: i32, msg: impl Into<String>) -> Self {
+        StreamResponse {
+            id,
+            result: None,
+            error: Some(StreamError {
+                code,
+                msg: msg.into(),
+            }),
+        }
+    }
+
+    /// The ack in the shape of a WS API response, a null result standing for the
+    /// default one
+    pub fn into_ws_response(self) -> WsResponse<T> {
+        match self.error {
+            None => WsResponse {
+                id: self.id,
+                status: 200,
+                error: None,
+                result: Some(self.result.unwrap_or_default()),
+                rate_limits: Vec::new(),
+            },
+            Some(error) => WsResponse {
+                id: self.id,
+                status: 400,
+                error: Some(WsError {
+                    code: error.code.into(),
+                    msg: error.msg,
+                    data: None,
+                }),
+                result: None,
+                rate_limits: Vec::new(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_id() {
+        let uuid = "6e3b2ebb-14a4-4b5b-8fd5-b3d5e7c1d8a1";
+        let ids: Vec<RequestId> = serde_json::from_str(&format!(r#"[7, "{uuid}"]"#)).unwrap();
+        assert_eq!(
+            ids,
+            [RequestId::Number(7), RequestId::Uuid(uuid.parse().unwrap())]
+        );
+        assert_eq!(
+            serde_json::to_string(&ids).unwrap(),
+            format!(r#"[7,"{uuid}"]"#)
+        );
+        assert_eq!(ids[1].to_string(), uuid);
+        assert!(serde_json::from_str::<RequestId>(r#""abc""#).is_err());
+
+        let (RequestId::Number(first), RequestId::Number(second)) =
+            (RequestId::next_number(), RequestId::next_number())
+        else {
+            panic!("stream request ids are numbers");
+        };
+        assert!(second > first);
+    }
+
+    #[test]
+    fn stream_ack() {
+        // a stream ack carries no status, the header still decodes to route it
+        let header: WsResponseHeader = serde_json::from_str(r#"{"result":null,"id":3}"#).unwrap();
+        assert_eq!((header.id, header.status), (RequestId::Number(3), 0));
+        assert!(header.error.is_none());
+
+        let ack: StreamResponse<Vec<String>> =
+            serde_json::from_str(r#"{"result":null,"id":3}"#).unwrap();
+        let response = ack.into_ws_response();
+        assert_eq!((response.id, response.status), (RequestId::Number(3), 200));
+        assert_eq!(response.result, Some(Vec::new()));
+
+        let ack: StreamResponse<Vec<String>> =
+            serde_json::from_str(r#"{"result":["btcusdt@aggTrade"],"id":4}"#).unwrap();
+        assert_eq!(
+            ack.into_ws_response().result,
+            Some(vec!["btcusdt@aggTrade".to_string()])
+        );
+
+        let ack: StreamResponse<Vec<String>> = serde_json::from_str(
+            r#"{"error":{"code":2,"msg":"Invalid request: unknown variable"},"id":5}"#,
+        )
+        .unwrap();
+        let response = ack.into_ws_response();
+        assert_eq!((response.id, response.status), (RequestId::Number(5), 400));
+        assert!(response.result.is_none());
+        let error = response.error.unwrap();
+        assert_eq!(error.code.code(), 2);
+        assert_eq!(error.msg, "Invalid request: unknown variable");
+    }
 }
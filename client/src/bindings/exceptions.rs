@@ -0,0 +1,57 @@
+// pyo3 0.18 `create_exception!` expands to a cfg unknown to newer compilers
+#![allow(unexpected_cfgs)]
+
+use crate::Error;
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+
+create_exception!(arbitrage_processing, BinanceException, PyException);
+create_exception!(arbitrage_processing, ConnectionException, BinanceException);
+create_exception!(arbitrage_processing, ProtocolException, BinanceException);
+create_exception!(
+    arbitrage_processing,
+    SerializationException,
+    BinanceException
+);
+create_exception!(arbitrage_processing, ApiException, BinanceException);
+create_exception!(arbitrage_processing, TimeoutException, BinanceException);
+create_exception!(arbitrage_processing, RateLimitException, BinanceException);
+create_exception!(arbitrage_processing, FilterException, BinanceException);
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> Self {
+        let msg = err.to_string();
+        match err {
+            Error::Connection(_) | Error::NotConnected | Error::Disconnected | Error::Lost(_) => {
+                ConnectionException::new_err(msg)
+            }
+            Error::Protocol(_)
+            | Error::Io(_)
+            | Error::UnknownRequest(_)
+            | Error::OutOfSync { .. } => ProtocolException::new_err(msg),
+            Error::Serialization(_) | Error::QueryString(_) => SerializationException::new_err(msg),
+            Error::Api { code, .. } => ApiException::new_err((code.code(), msg)),
+            Error::MissingResult { .. } => ApiException::new_err(msg),
+            Error::Timeout => TimeoutException::new_err(msg),
+            Error::RateLimited { retry_after } => {
+                RateLimitException::new_err((retry_after.as_secs_f64(), msg))
+            }
+            Error::UnknownSymbol(_) | Error::Filter { .. } => FilterException::new_err(msg),
+        }
+    }
+}
+
+pub(super) fn register(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("BinanceException", py.get_type::<BinanceException>())?;
+    m.add("ConnectionException", py.get_type::<ConnectionException>())?;
+    m.add("ProtocolException", py.get_type::<ProtocolException>())?;
+    m.add(
+        "SerializationException",
+        py.get_type::<SerializationException>(),
+    )?;
+    m.add("ApiException", py.get_type::<ApiException>())?;
+    m.add("TimeoutException", py.get_type::<TimeoutException>())?;
+    m.add("RateLimitException", py.get_type::<RateLimitException>())?;
+    m.add("FilterException", py.get_type::<FilterException>())?;
+
+    Ok(())
+}
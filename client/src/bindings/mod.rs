@@ -1,14 +1,27 @@
 #![allow(unused_imports)]
 use crate::{
     api::{
-        order::{OrderRequest, OrderResponse},
+        account::{AccountCommissionRequest, AccountStatusRequest, Balance},
+        decimal::{Decimal, ParseDecimalError, Qty},
+        general::ExchangeInfoRequest,
+        order::{Fill, OrderRequest, OrderResponse, OrderResponseFull},
+        order_list::{
+            OrderListCancelRequest, OrderListEntry, OrderListPlaceOcoRequest, OrderListResponse,
+            OrderListStatusRequest, OrderReport,
+        },
         secret_key::{self, SecretKey},
-        subscription::{SubscribeRequest, SubscribeResponse, SubscriptionUpdate},
-        types::{OrderStatus, OrderType, Side},
-        BinanceRequest, ws::WsResponse,
+        subscription::{AggTrade, KlineUpdate, SubscribeRequest, SubscribeResponse, SubscriptionUpdate, Trade, UnsubscribeRequest},
+        types::{KlineInterval, OrderStatus, OrderType, RateLimit, Side},
+        user_data::{ExecutionReport, UserDataEvent},
+        utils,
+        BinanceRequest, RequestPayload, ws::WsResponse,
     },
-    client::BinanceClient,
-    triangles::Triangle,
+    candles::{Candle, Candles},
+    discovery::{self, Discovery},
+    client::{Balances, BinanceClient, Commissions, Event, Keepalive, LimitMode, RateLimiter, RateLimits, ReconnectPolicy, StreamDispatcher, SymbolRules},
+    tape::{TapeStats, Tapes},
+    triangles::{FlowFilter, Order, Triangle},
+    Error, Result,
 };
 use ahash::{HashMap, HashSet};
 use awc::ws::Frame;
@@ -21,8 +34,12 @@ use rand::random;
 use std::{
     cell::RefCell,
     hash::Hash,
+    str::FromStr,
     rc::Rc,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
     thread,
     thread::JoinHandle,
     // thread,
@@ -33,28 +50,353 @@ use tokio::{
     runtime::{Builder, Runtime},
     select,
     sync::{
-        mpsc::{channel, unbounded_channel, Sender, UnboundedReceiver},
-        Mutex as TokioMutex, Notify,
+        broadcast,
+        mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+        oneshot, Mutex as TokioMutex, Notify,
     },
     task::{self, LocalSet},
 };
 
+mod exceptions;
+
+const TIME_SYNC_INTERVAL: Duration = Duration::from_secs(60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const LISTEN_KEY_KEEPALIVE: Duration = Duration::from_secs(30 * 60);
+const EXECUTION_REPORTS: usize = 1024;
+const TAPE_CAPACITY: usize = 1024;
+/// Window of the trade tape stats, in milliseconds
+const TAPE_WINDOW: u64 = 5_000;
+/// Candle length unless the client is created with another one
+const DEFAULT_CANDLE_INTERVAL: KlineInterval = KlineInterval::M1;
+/// Closed candles kept per symbol
+const CANDLE_HISTORY: usize = 240;
+
+/// Triangles handed to a worker at once, their streams go in a single request
 #[derive(Debug)]
 enum Subscription {
-    Subscribe(Triangle),
+    Subscribe(Vec<Triangle>),
+    Unsubscribe(Vec<Triangle>),
+}
+
+/// Requests issued from Python and run on a worker's order connection
+enum Command {
+    PlaceOco(OrderListPlaceOcoRequest, oneshot::Sender<Result<OrderListResponse>>),
+    CancelOrderList(OrderListCancelRequest, oneshot::Sender<Result<OrderListResponse>>),
+    OrderListStatus(OrderListStatusRequest, oneshot::Sender<Result<OrderListResponse>>),
+    DiscoverTriangles(Discovery, oneshot::Sender<Result<Vec<Triangle>>>),
+}
+
+impl Command {
+    async fn run(self, client: &mut BinanceClient, secret_key: &SecretKey) {
+        // the caller may have stopped waiting
+        let _ = match self {
+            Command::PlaceOco(request, sender) => sender.send(signed(client, secret_key, request).await),
+            Command::CancelOrderList(request, sender) => sender.send(signed(client, secret_key, request).await),
+            Command::OrderListStatus(request, sender) => sender.send(signed(client, secret_key, request).await),
+            Command::DiscoverTriangles(discovery, sender) => {
+                // the response loads the rules of every symbol along the way, only spot
+                // symbols make triangles
+                let request = ExchangeInfoRequest::permissions(vec!["SPOT".into()]);
+                let symbols = client.request(request).await;
+                let _ = sender.send(symbols.map(|info| discovery.triangles(&info.symbols)));
+                Ok(())
+            }
+        };
+    }
+}
+
+async fn signed<T>(client: &mut BinanceClient, secret_key: &SecretKey, mut request: T) -> Result<T::Response>
+where
+    T: BinanceRequest + RequestPayload,
+{
+    secret_key.sign(&mut request)?;
+    client.request(request).await
+}
+
+fn parse_side(side: &str) -> PyResult<Side> {
+    match side.to_uppercase().as_str() {
+        "BUY" => Ok(Side::Buy),
+        "SELL" => Ok(Side::Sell),
+        _ => Err(pyo3::exceptions::PyValueError::new_err(format!("invalid side: {side}"))),
+    }
+}
+
+/// Symbol of a stream name, e.g. BTCUSDT for `btcusdt@aggTrade`
+fn stream_symbol(stream: &str) -> Option<String> {
+    stream.split_once('@').map(|(symbol, _)| symbol.to_uppercase())
+}
+
+fn parse_decimal<T: FromStr<Err = ParseDecimalError>>(value: &str) -> PyResult<T> {
+    value
+        .parse()
+        .map_err(|err| pyo3::exceptions::PyValueError::new_err(format!("{err}: {value}")))
+}
+
+/// Host part of a stream url, the combined endpoint lives under `/stream` while
+/// single streams such as the user data one live under `/ws`. Either is stripped.
+fn stream_base(streams_url: &str) -> &str {
+    let url = streams_url.split('?').next().unwrap_or_default().trim_end_matches('/');
+    url.strip_suffix("/stream")
+        .or_else(|| url.strip_suffix("/ws"))
+        .unwrap_or(url)
+}
+
+/// Keep a user data stream open until `shutdown` fires, applying the balance updates
+/// to the cache and broadcasting the execution reports
+fn spawn_user_data(
+    url: String,
+    streams_url: String,
+    api_key: String,
+    timeout: Duration,
+    balances: Balances,
+    executions: broadcast::Sender<ExecutionReport>,
+    mut shutdown: oneshot::Receiver<()>,
+) -> JoinHandle<()> {
+    thread::Builder::new()
+        .name("user data".into())
+        .spawn(move || {
+            let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+            let local = LocalSet::new();
+            local.block_on(&runtime, async move {
+                let mut client = BinanceClient::new(url);
+                client.set_reconnect_policy(Some(ReconnectPolicy::default()));
+                client.set_keepalive(Some(Keepalive::default()));
+                if let Err(err) = client.connect(timeout).await {
+                    log::error!("[user data]: failed to connect: {err}");
+                    return;
+                }
+                // a new session starts whenever the listen key expires
+                'session: loop {
+                    let listen_key = match client.start_user_data_stream(&api_key, LISTEN_KEY_KEEPALIVE).await {
+                        Ok(listen_key) => listen_key,
+                        Err(err) => {
+                            log::error!("[user data]: failed to start the stream: {err}");
+                            return;
+                        }
+                    };
+                    let mut stream = BinanceClient::new(format!("{}/ws/{listen_key}", stream_base(&streams_url)));
+                    stream.set_reconnect_policy(Some(ReconnectPolicy::default()));
+                    stream.set_keepalive(Some(Keepalive::default()));
+                    if let Err(err) = stream.connect(timeout).await {
+                        log::error!("[user data]: failed to connect to the stream: {err}");
+                        return;
+                    }
+                    loop {
+                        select! {
+                            _ = &mut shutdown => {
+                                if let Err(err) = client.stop_user_data_stream().await {
+                                    log::warn!("[user data]: failed to stop the stream: {err}");
+                                }
+                                return;
+                            }
+                            event = client.next() => match event {
+                                Ok(Some(Event::ListenKeyExpired { .. })) => continue 'session,
+                                Ok(Some(_)) => {}
+                                Ok(None) => {
+                                    log::error!("[user data]: connection closed");
+                                    return;
+                                }
+                                Err(err) => log::error!("[user data]: {err}"),
+                            },
+                            event = stream.next() => match event {
+                                Ok(Some(Event::Response { bytes, .. })) => match serde_json::from_slice::<UserDataEvent>(&bytes) {
+                                    Ok(UserDataEvent::ExecutionReport(report)) => {
+                                        // nobody may be listening
+                                        let _ = executions.send(report);
+                                    }
+                                    Ok(UserDataEvent::OutboundAccountPosition(position)) => balances.update(&position.balances),
+                                    Ok(UserDataEvent::ListenKeyExpired(_)) => continue 'session,
+                                    Ok(event) => log::debug!("[user data]: {event:?}"),
+                                    Err(err) => log::warn!("[user data]: unexpected event: {err}"),
+                                },
+                                Ok(Some(_)) => {}
+                                Ok(None) => {
+                                    log::error!("[user data]: stream closed");
+                                    return;
+                                }
+                                Err(err) => log::error!("[user data]: {err}"),
+                            },
+                        }
+                    }
+                }
+            });
+        })
+        .unwrap()
+}
+
+/// Wait for the final execution report of an order, skipping the reports of others
+async fn wait_fill(
+    executions: &mut broadcast::Receiver<ExecutionReport>,
+    client_order_id: &str,
+) -> Result<ExecutionReport> {
+    loop {
+        match executions.recv().await {
+            Ok(report) if report.client_order_id == client_order_id => match report.order_status {
+                OrderStatus::New | OrderStatus::PartiallyFilled | OrderStatus::PendingCancel => {}
+                _ => return Ok(report),
+            },
+            Ok(_) => {}
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                log::warn!("Skipped {skipped} execution reports")
+            }
+            Err(broadcast::error::RecvError::Closed) => return Err(Error::Disconnected),
+        }
+    }
+}
+
+/// Place the first leg and, once it is filled, the two others. Test orders are
+/// only validated, so the two others follow as soon as the first one passes.
+/// With the user data stream the fill of the first leg is taken from its
+/// execution report or its reply, whichever arrives first.
+async fn execute(
+    client: &mut BinanceClient,
+    api_key: &str,
+    secret_key: &SecretKey,
+    (leg1, leg2, leg3): (Order, Order, Order),
+    test: bool,
+    executions: Option<&mut broadcast::Receiver<ExecutionReport>>,
+    results_sender: &UnboundedSender<Result<OrderResponseFull>>,
+) -> Result<()> {
+    let symbols = [&leg1.symbol, &leg2.symbol, &leg3.symbol];
+    if !symbols.iter().all(|symbol| client.symbol_rules().contains(symbol)) {
+        let symbols = symbols.into_iter().cloned().collect();
+        client.request(ExchangeInfoRequest::symbols(symbols)).await?;
+    }
+
+    // the full responses carry the fills with their actual prices and fees
+    let mut order_request = OrderRequest::new(
+        api_key,
+        &leg1.symbol,
+        leg1.action,
+        OrderType::Market,
+        Some(leg1.amt),
+    )
+    .full();
+    let mut order_request_2 = OrderRequest::new(
+        api_key,
+        &leg2.symbol,
+        leg2.action,
+        OrderType::Market,
+        Some(leg2.amt),
+    )
+    .full();
+    let mut order_request_3 = OrderRequest::new(
+        api_key,
+        &leg3.symbol,
+        leg3.action,
+        OrderType::Market,
+        Some(leg3.amt),
+    )
+    .full();
+
+    order_request.test = test;
+    order_request_2.test = test;
+    order_request_3.test = test;
+
+    // Binance rejects amounts off the lot step, round them before signing
+    let rules = client.symbol_rules().clone();
+    rules.conform(&mut order_request, Some(leg1.price))?;
+    rules.conform(&mut order_request_2, Some(leg2.price))?;
+    rules.conform(&mut order_request_3, Some(leg3.price))?;
+
+    // test replies are empty, tell Python which leg they belong to
+    let named = |mut response: OrderResponseFull, symbol: &str| {
+        if test {
+            response.symbol = symbol.into();
+        }
+        response
+    };
+
+    let client_order_id = random::<u32>();
+    order_request.new_client_order_id = Some(client_order_id);
+    let executions = executions.filter(|_| !test).map(|executions| {
+        // only the reports from now on are of interest
+        *executions = executions.resubscribe();
+        executions
+    });
+
+    secret_key.sign(&mut order_request)?;
+    let handle = client.send(order_request.preprocess()?).await?;
+
+    secret_key.sign(&mut order_request_2)?;
+    secret_key.sign(&mut order_request_3)?;
+    let order_request_2 = order_request_2.preprocess()?;
+    let order_request_3 = order_request_3.preprocess()?;
+
+    let (filled, response) = match executions {
+        Some(executions) => {
+            let client_order_id = client_order_id.to_string();
+            let fill = tokio::time::timeout(REQUEST_TIMEOUT, wait_fill(executions, &client_order_id));
+            // a rejected order gets no report, only an error reply
+            select! {
+                report = fill => {
+                    let report = report.map_err(|_| Error::Timeout)??;
+                    (matches!(report.order_status, OrderStatus::Filled), None)
+                }
+                response = client.result_of(&handle) => {
+                    let response = named(response?, &leg1.symbol);
+                    (matches!(response.status, Some(OrderStatus::Filled)), Some(response))
+                }
+            }
+        }
+        None => {
+            let response = named(client.result_of(&handle).await?, &leg1.symbol);
+            (test || matches!(response.status, Some(OrderStatus::Filled)), Some(response))
+        }
+    };
+
+    // conditionally send the next two orders
+    if filled {
+        let handle_2 = client.feed(order_request_2).await?;
+        let handle_3 = client.feed(order_request_3).await?;
+        client.flush().await?;
+
+        let response = match response {
+            Some(response) => response,
+            None => client.result_of(&handle).await?,
+        };
+        let _ = results_sender.send(Ok(response));
+
+        // the replies may arrive in any order
+        for (handle, symbol) in [(handle_2, &leg2.symbol), (handle_3, &leg3.symbol)] {
+            let result = client.result_of(&handle).await;
+            let _ = results_sender.send(result.map(|response| named(response, symbol)));
+        }
+    } else if response.is_none() {
+        // the reply to a leg that did not fill is of no use
+        client.cancel(handle.id());
+    }
+
+    Ok(())
 }
 
 #[pyclass]
 pub struct Client {
     threads: Vec<JoinHandle<()>>,
-    subscription_senders: Vec<Sender<Subscription>>,
+    subscription_senders: Vec<UnboundedSender<Subscription>>,
     next_sender: usize,
-    results_reciever: Arc<TokioMutex<UnboundedReceiver<OrderResponse>>>,
+    /// Worker each subscribed triangle was handed to
+    assignments: HashMap<Triangle, usize>,
+    command_senders: Vec<UnboundedSender<Command>>,
+    next_command: usize,
+    api_key: String,
+    results_reciever: Arc<TokioMutex<UnboundedReceiver<Result<OrderResponseFull>>>>,
+    rate_limits: RateLimits,
+    balances: Balances,
+    /// Most of each quote asset a triangle may trade, its whole free balance without one
+    max_bags: Arc<Mutex<HashMap<String, Qty>>>,
+    test_orders: Arc<AtomicBool>,
+    tapes: Tapes,
+    flow_filter: Arc<Mutex<Option<FlowFilter>>>,
+    candles: Candles,
+    executions_reciever: Arc<TokioMutex<broadcast::Receiver<ExecutionReport>>>,
+    user_data_shutdown: Option<oneshot::Sender<()>>,
 }
 
 #[pymethods]
 impl Client {
     #[new]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         url: String,
         streams_url: String,
@@ -62,69 +404,259 @@ impl Client {
         secret_key: &str,
         thread_num: usize,
         timeout: Option<u64>,
-    ) -> Self {
+        user_data_stream: Option<bool>,
+        trade_tape: Option<bool>,
+        candle_interval: Option<&str>,
+    ) -> PyResult<Self> {
         let secret_key = SecretKey::new(secret_key);
-        let (results_sender, results_reciever) = unbounded_channel::<OrderResponse>();
+        let (results_sender, results_reciever) = unbounded_channel::<Result<OrderResponseFull>>();
+        // all workers connect from the same IP and share its limits
+        let rate_limits = RateLimits::new();
+        // and trade on the same account
+        let balances = Balances::new();
+        let commissions = Commissions::new();
+        let max_bags: Arc<Mutex<HashMap<String, Qty>>> = Default::default();
+        // on the same symbols
+        let symbol_rules = SymbolRules::new();
+        let test_orders = Arc::new(AtomicBool::new(false));
+        let tapes = Tapes::new(TAPE_CAPACITY, TAPE_WINDOW);
+        // streams subscribed by every worker per symbol, the shared tapes and candles
+        // of a symbol are dropped with the last one
+        let watched: Arc<Mutex<HashMap<String, usize>>> = Default::default();
+        let flow_filter: Arc<Mutex<Option<FlowFilter>>> = Default::default();
+        let candle_interval = match candle_interval {
+            Some(interval) => KlineInterval::parse(interval).ok_or_else(|| {
+                pyo3::exceptions::PyValueError::new_err(format!("invalid candle interval: {interval}"))
+            })?,
+            None => DEFAULT_CANDLE_INTERVAL,
+        };
+        // months vary in length
+        let candles = candle_interval
+            .millis()
+            .and_then(|millis| Candles::new(millis, CANDLE_HISTORY))
+            .ok_or_else(|| {
+                pyo3::exceptions::PyValueError::new_err(format!(
+                    "candle interval without a fixed length: {}",
+                    candle_interval.as_str()
+                ))
+            })?;
+        // the aggregated trades of every leg feed the tapes
+        let trade_tape = trade_tape.unwrap_or(false);
+        let user_data_stream = user_data_stream.unwrap_or(false);
+        let (executions, executions_reciever) = broadcast::channel(EXECUTION_REPORTS);
 
-        let (subscription_senders, threads) = (0..thread_num)
+        let mut command_senders = Vec::with_capacity(thread_num);
+        let (subscription_senders, mut threads): (Vec<_>, Vec<_>) = (0..thread_num)
             .map(|i| {
                 // TODO: break this function into small pieces
-                let (subscriptions_sender, mut subscriptions_reciever) = channel(100);
+                let (subscriptions_sender, mut subscriptions_reciever) = unbounded_channel::<Subscription>();
+                let (command_sender, mut commands_reciever) = unbounded_channel::<Command>();
+                command_senders.push(command_sender);
                 let results_sender = results_sender.clone();
                 let api_key = api_key.clone();
                 let secret_key = secret_key.clone();
                 let url = url.clone();
                 let streams_url = streams_url.clone();
+                let rate_limits = rate_limits.clone();
+                let balances = balances.clone();
+                let commissions = commissions.clone();
+                let max_bags = max_bags.clone();
+                let symbol_rules = symbol_rules.clone();
+                let test_orders = test_orders.clone();
+                let tapes = tapes.clone();
+                let watched = watched.clone();
+                let flow_filter = flow_filter.clone();
+                let candles = candles.clone();
+                let mut executions = user_data_stream.then(|| executions.subscribe());
                 let handle = thread::Builder::new()
                     .name(format!("worker {i}"))
                     .spawn(move || {
                         let runtime = Builder::new_current_thread().enable_all().build().unwrap();
                         let mut client = BinanceClient::new(url);
                         let mut ticker = BinanceClient::new(streams_url);
+                        client.set_reconnect_policy(Some(ReconnectPolicy::default()));
+                        client.set_rate_limits(rate_limits);
+                        client.set_balances(balances.clone());
+                        client.set_commissions(commissions.clone());
+                        client.set_symbol_rules(symbol_rules);
+                        client.set_time_sync(Some(TIME_SYNC_INTERVAL));
+                        client.set_keepalive(Some(Keepalive::default()));
+                        client.set_request_timeout(Some(REQUEST_TIMEOUT));
+                        ticker.set_reconnect_policy(Some(ReconnectPolicy::default()));
+                        ticker.set_keepalive(Some(Keepalive::default()));
                         let updates: Rc<RefCell<HashMap<String, SubscriptionUpdate>>> = Default::default();
                         let update_notify = Rc::new(Notify::new());
                         let triangles: Rc<RefCell<HashSet<Triangle>>> = Default::default();
                         let local = LocalSet::new();
                         local.block_on(&runtime, async move {
-                            join!(
+                            let (connected, ticker_connected) = join!(
                                 client.connect(Duration::new(timeout.unwrap_or(5), 0)),
                                 ticker.connect(Duration::new(timeout.unwrap_or(5), 0)),
                             );
+                            if let Err(err) = connected.and(ticker_connected) {
+                                log::error!("[{i}]: failed to connect: {err}");
+                                return;
+                            }
+                            if let Err(err) = signed(&mut client, &secret_key, AccountStatusRequest::new(&api_key)).await {
+                                log::error!("[{i}]: failed to load balances: {err}");
+                            }
                             let _ticker = {
                                 let updates = updates.clone();
                                 let triangles = triangles.clone();
                                 let update_notify = update_notify.clone();
+                                let results_sender = results_sender.clone();
+                                let tapes = tapes.clone();
+                                let candles = candles.clone();
                                 task::spawn_local(async move {
                                 // println!("[{i}]: starting update_subs");
+                                // triangles can share pairs, a stream is only dropped with the last one
+                                let mut streams: HashMap<String, usize> = HashMap::default();
+                                let mut dispatcher = StreamDispatcher::new();
+                                {
+                                    let updates = updates.clone();
+                                    let update_notify = update_notify.clone();
+                                    let mids = candles.clone();
+                                    dispatcher.on("bookTicker", move |_stream, update: SubscriptionUpdate| {
+                                        mids.record_book_ticker(&update, utils::server_timestamp());
+                                        updates.borrow_mut().insert(update.symbol.clone(), update);
+                                        update_notify.notify_one();
+                                    });
+                                    let klines = candles.clone();
+                                    dispatcher.on(&format!("kline_{}", candle_interval.as_str()), move |_stream, update: KlineUpdate| klines.record_kline(&update));
+                                    let trades = tapes.clone();
+                                    dispatcher.on("trade", move |_stream, trade: Trade| trades.record_trade(&trade));
+                                    let trades = tapes.clone();
+                                    dispatcher.on("aggTrade", move |_stream, trade: AggTrade| trades.record_agg_trade(&trade));
+                                }
+                                let tri_streams = |tri: &Triangle| {
+                                    let mut streams = tri.streams();
+                                    if trade_tape {
+                                        streams.extend(tri.trade_streams());
+                                    }
+                                    streams
+                                };
                                 loop {
                                     // println!("[{i}]: polling");
                                     select! {
                                         Some(sub) = subscriptions_reciever.recv() => {
                                             println!("[{i}]: recieved {sub:?}");
                                             match sub {
-                                                Subscription::Subscribe(tri) => {
-                                                    triangles.borrow_mut().insert(tri.clone());
-                                                    let Triangle {base, quote, alt, ..} = tri;
-                                                    let mut subs = vec![
-                                                        format!("{base}{quote}@bookTicker"),
-                                                        format!("{alt}{base}@bookTicker"),
-                                                        format!("{alt}{quote}@bookTicker")
-                                                    ];
-                                                    if quote != "USDT" {
-                                                        // TODO: avoid duplicating requests?
-                                                        subs.push(format!("{quote}USDT@bookTicker"));
+                                                Subscription::Subscribe(tris) => {
+                                                    let tris = tris.into_iter().filter(|tri| triangles.borrow_mut().insert(tri.clone())).collect::<Vec<_>>();
+                                                    let subs = tris.iter().flat_map(tri_streams).filter(|stream| {
+                                                        let count = streams.entry(stream.clone()).or_default();
+                                                        *count += 1;
+                                                        *count == 1
+                                                    }).collect::<Vec<_>>();
+                                                    if subs.is_empty() {
+                                                        continue;
                                                     }
+                                                    let symbols = subs.iter().filter_map(|stream| stream_symbol(stream)).collect::<Vec<_>>();
                                                     let request = SubscribeRequest::new(subs);
-                                                    let request = request.preprocess().unwrap();
-                                                    ticker.send(request).await.unwrap();
+                                                    let acked = match ticker.subscribe(request).await {
+                                                        Ok(handle) => ticker.result_of(&handle).await.map(|_| ()),
+                                                        Err(err) => Err(err),
+                                                    };
+                                                    if acked.is_ok() {
+                                                        let mut watched = watched.lock().unwrap_or_else(|err| err.into_inner());
+                                                        for symbol in symbols {
+                                                            *watched.entry(symbol).or_default() += 1;
+                                                        }
+                                                    }
+                                                    if let Err(err) = acked {
+                                                        log::error!("[{i}]: failed to subscribe: {err}");
+                                                        // undo the bookkeeping so a later attempt subscribes again
+                                                        for tri in &tris {
+                                                            triangles.borrow_mut().remove(tri);
+                                                            for stream in tri_streams(tri) {
+                                                                if let Some(count) = streams.get_mut(&stream) {
+                                                                    *count -= 1;
+                                                                    if *count == 0 {
+                                                                        streams.remove(&stream);
+                                                                    }
+                                                                }
+                                                            }
+                                                        }
+                                                        let _ = results_sender.send(Err(err));
+                                                    }
                                                     // println!("[{i}]: sent subscribe");
                                                 }
+                                                Subscription::Unsubscribe(tris) => {
+                                                    let unsubs = tris.iter().filter(|tri| triangles.borrow_mut().remove(tri)).flat_map(tri_streams).filter(|stream| {
+                                                        let Some(count) = streams.get_mut(stream) else {
+                                                            return false;
+                                                        };
+                                                        *count -= 1;
+                                                        if *count > 0 {
+                                                            return false;
+                                                        }
+                                                        streams.remove(stream);
+                                                        true
+                                                    }).collect::<Vec<_>>();
+                                                    if unsubs.is_empty() {
+                                                        continue;
+                                                    }
+                                                    {
+                                                        // forget the prices and trades, they will go stale
+                                                        let mut updates = updates.borrow_mut();
+                                                        let mut watched = watched.lock().unwrap_or_else(|err| err.into_inner());
+                                                        for symbol in unsubs.iter().filter_map(|stream| stream_symbol(stream)) {
+                                                            updates.remove(&symbol);
+                                                            // unless another worker still watches the symbol
+                                                            let Some(count) = watched.get_mut(&symbol) else {
+                                                                continue;
+                                                            };
+                                                            *count -= 1;
+                                                            if *count == 0 {
+                                                                watched.remove(&symbol);
+                                                                tapes.remove(&symbol);
+                                                                candles.remove(&symbol);
+                                                            }
+                                                        }
+                                                    }
+                                                    let request = UnsubscribeRequest::new(unsubs);
+                                                    // the ack is checked once next() yields it, the loop must not wait on it
+                                                    if let Err(err) = ticker.unsubscribe(request).await {
+                                                        log::error!("[{i}]: failed to unsubscribe: {err}");
+                                                        let _ = results_sender.send(Err(err));
+                                                    }
+                                                }
                                             }
 
                                         }
-                                        Ok(Some((_ ,_, response))) = ticker.next() => {
+                                        Ok(Some(event)) = ticker.next() => {
+                                            let response = match event {
+                                                // data frames carry no id
+                                                Event::Response { id: None, bytes, .. } => bytes,
+                                                // acks of the subscriptions replayed after a reconnect
+                                                Event::Response { method: Some(SubscribeRequest::METHOD), bytes, .. } => {
+                                                    if let Err(err) = SubscribeRequest::decode(&bytes).and_then(Error::from_response) {
+                                                        log::error!("[{i}]: failed to resubscribe: {err}");
+                                                        let _ = results_sender.send(Err(err));
+                                                    }
+                                                    continue;
+                                                }
+                                                Event::Response { method: Some(UnsubscribeRequest::METHOD), bytes, .. } => {
+                                                    if let Err(err) = UnsubscribeRequest::decode(&bytes).and_then(Error::from_response) {
+                                                        log::error!("[{i}]: failed to unsubscribe: {err}");
+                                                        let _ = results_sender.send(Err(err));
+                                                    }
+                                                    continue;
+                                                }
+                                                _ => continue,
+                                            };
+                                            match dispatcher.dispatch(&response) {
+                                                Ok(true) => continue,
+                                                Ok(false) => {}
+                                                Err(err) => {
+                                                    log::error!("[{i}]: failed to decode a stream frame: {err}");
+                                                    continue;
+                                                }
+                                            }
+                                            // raw payloads of a single stream connection
                                             let update = serde_json::from_slice::<SubscriptionUpdate>(&response);
                                             if let Ok(update) = update {
+                                                candles.record_book_ticker(&update, utils::server_timestamp());
                                                 let mut updates = updates.borrow_mut();
                                                 updates.insert(update.symbol.clone(), update);
                                                 // println!("[{i}]: updates: {updates:#?}",);
@@ -138,105 +670,67 @@ impl Client {
                                 // println!("[{i}]: giving up")
                                 })
                             };
+                            let mut requested_commissions: HashSet<String> = HashSet::default();
                             loop {
                                 // TODO: crunch more triangles if we stopped halfway through
-                                update_notify.notified().await;
+                                // keep the order connection alive while waiting for updates
+                                select! {
+                                    _ = update_notify.notified() => {}
+                                    Some(command) = commands_reciever.recv() => {
+                                        command.run(&mut client, &secret_key).await;
+                                        continue;
+                                    }
+                                    event = client.next() => {
+                                        match event {
+                                            Ok(Some(Event::Disconnected { reason })) => log::warn!("[{i}]: disconnected: {reason:?}"),
+                                            Ok(Some(Event::Reconnected { lost })) => log::warn!("[{i}]: reconnected, lost {lost:?}"),
+                                            Ok(Some(Event::TimedOut { id, method })) => log::warn!("[{i}]: {method} request {id} timed out"),
+                                            Ok(Some(_)) => {}
+                                            Ok(None) => {
+                                                log::error!("[{i}]: order connection closed");
+                                                break;
+                                            }
+                                            Err(err) => log::error!("[{i}]: {err}"),
+                                        }
+                                        continue;
+                                    }
+                                }
+
+                                // the commissions eat into the profit, load them once per symbol
+                                let missing = triangles.borrow().iter()
+                                    .flat_map(|tri| [format!("{}{}", tri.base, tri.quote), format!("{}{}", tri.alt, tri.base), format!("{}{}", tri.alt, tri.quote)])
+                                    .filter(|symbol| !commissions.contains(symbol) && requested_commissions.insert(symbol.clone()))
+                                    .collect::<Vec<_>>();
+                                for symbol in missing {
+                                    if let Err(err) = signed(&mut client, &secret_key, AccountCommissionRequest::new(&api_key, &symbol)).await {
+                                        log::error!("[{i}]: failed to load the commission of {symbol}: {err}");
+                                    }
+                                }
 
                                 // println!("[{i}: checking tris]");
                                 let tri = {
                                     let updates = updates.borrow();
-                                    // TODO: bag amount handling
-                                    triangles.borrow().iter().find_map(|tri| tri.crunch(&updates, 100.))
+                                    let max_bags = max_bags.lock().unwrap_or_else(|err| err.into_inner()).clone();
+                                    let bag = |asset: &str| {
+                                        let free = balances.free(asset);
+                                        max_bags.get(asset).map_or(free, |max| free.min(*max))
+                                    };
+                                    triangles.borrow().iter().find_map(|tri| tri.crunch(&updates, bag(&tri.quote), &commissions))
                                 };
 
-                                if let Some((leg1, leg2, leg3)) = tri {
-                                    // println!("[{i}]: Found triangle opportunity: {} -- {} -- {}", leg1.symbol, leg2.symbol, leg3.symbol);
-                                    let mut order_request = OrderRequest::new(
-                                        &api_key,
-                                        &leg1.symbol,
-                                        leg1.action,
-                                        OrderType::Market,
-                                        Some(leg1.amt),
-                                    );
-                                    let mut order_request_2 = OrderRequest::new(
-                                        &api_key,
-                                        &leg2.symbol,
-                                        leg2.action,
-                                        OrderType::Market,
-                                        Some(leg2.amt),
-                                    );
-
-                                    let mut order_request_3 = OrderRequest::new(
-                                        &api_key,
-                                        &leg3.symbol,
-                                        leg3.action,
-                                        OrderType::Market,
-                                        Some(leg3.amt),
-                                    );
-
-                                    secret_key.sign(&mut order_request);
-                                    
-                                    let order_request = order_request.preprocess().unwrap();
-                                    client.send(order_request).await.unwrap();
-
-                                    secret_key.sign(&mut order_request_2);
-                                    secret_key.sign(&mut order_request_3);
-                                    let order_request_2 = order_request_2.preprocess().unwrap();
-                                    let order_request_3 = order_request_3.preprocess().unwrap();
-
-                                    let (_id, _method, response) =
-                                        client.next().await.unwrap().unwrap();
-
-                                    // println!("bytes: {response:?}");
-                                    let response = serde_json::from_slice::<WsResponse<OrderResponse>>(&response);
-                                    
-                                    // conditionally send the next two orders
-                                    if let Ok(
-                                        WsResponse {
-                                            result:
-                                            OrderResponse {
-                                                status: Some(OrderStatus::Filled),
-                                                ..
-                                            },
-                                            ..
-                                        }
-                                    ) = response
-                                    {
-                                        // alt options:
-                                        // ```
-                                        // if let Ok(response) = response {
-                                        //     if let Some(OrderStatus::Filled) = response.status {
-                                        // ```
-                                        // ```
-                                        // if let Ok(response) = response {
-                                        //     if matches!(response.status, Some(OrderStatus::Filled)) {
-                                        // ```
-                                        println!("sending next two orders");
-
-                                        client.feed(order_request_2).await.unwrap();
-                                        client.feed(order_request_3).await.unwrap();
-                                        client.flush().await.unwrap();
-
-                                        if let Ok(response) = &response {
-                                            // print!("sending {response:?}");
-                                            results_sender.send(response.result.clone()).unwrap();
-                                        }
-
-                                        let (_, _, response2) = client.next().await.unwrap().unwrap();
-                                        let (_, _, response3) = client.next().await.unwrap().unwrap();
-
-                                        let response2 = serde_json::from_slice::<WsResponse<OrderResponse>>(&response2);
-                                        let response3 = serde_json::from_slice::<WsResponse<OrderResponse>>(&response3);
-
-                                        if let Ok(response) = &response2 {
-                                            // print!("sending {response:?}");
-                                            results_sender.send(response.result.clone()).unwrap();
-                                        }
-                                        if let Ok(response) = &response3 {
-                                            // print!("sending {response:?}");
-                                            results_sender.send(response.result.clone()).unwrap();
-                                        }
+                                // skip opportunities against the trade flow
+                                let filter = *flow_filter.lock().unwrap_or_else(|err| err.into_inner());
+                                let tri = tri.filter(|legs| filter.is_none_or(|filter| filter.allows_legs(legs, &tapes)));
 
+                                if let Some(legs) = tri {
+                                    // println!("[{i}]: Found triangle opportunity: {} -- {} -- {}", leg1.symbol, leg2.symbol, leg3.symbol);
+                                    if let Err(err) = execute(&mut client, &api_key, &secret_key, legs, test_orders.load(Ordering::Relaxed), executions.as_mut(), &results_sender).await {
+                                        log::error!("[{i}]: {err}");
+                                        let _ = results_sender.send(Err(err));
+                                    }
+                                    // the legs moved the balances
+                                    if let Err(err) = signed(&mut client, &secret_key, AccountStatusRequest::new(&api_key)).await {
+                                        log::error!("[{i}]: failed to refresh balances: {err}");
                                     }
                                 }
                             }
@@ -247,14 +741,193 @@ impl Client {
                 (subscriptions_sender, handle)
             })
             .unzip();
-        Self {
+        let user_data_shutdown = user_data_stream.then(|| {
+            let (shutdown_sender, shutdown) = oneshot::channel();
+            threads.push(spawn_user_data(
+                url.clone(),
+                streams_url.clone(),
+                api_key.clone(),
+                Duration::new(timeout.unwrap_or(5), 0),
+                balances.clone(),
+                executions.clone(),
+                shutdown,
+            ));
+            shutdown_sender
+        });
+        Ok(Self {
             threads,
             subscription_senders,
             next_sender: 0,
+            assignments: HashMap::default(),
+            command_senders,
+            next_command: 0,
+            api_key,
             // url,
             // api_key,
             // secret_key,
             results_reciever: Arc::new(TokioMutex::new(results_reciever)),
+            rate_limits,
+            balances,
+            max_bags,
+            test_orders,
+            tapes,
+            flow_filter,
+            candles,
+            executions_reciever: Arc::new(TokioMutex::new(executions_reciever)),
+            user_data_shutdown,
+        })
+    }
+    /// Estimated server clock offset and the round trip time it was measured with,
+    /// both in milliseconds
+    pub fn clock(&self) -> (i64, u64) {
+        (utils::clock_offset(), utils::clock_rtt())
+    }
+    /// Last known balances of the account
+    pub fn balances(&self) -> Vec<Balance> {
+        self.balances.all()
+    }
+    /// Trade at most `amount` of the quote asset per triangle, or its whole free
+    /// balance with None
+    pub fn set_max_bag(&self, asset: &str, amount: Option<f64>) -> PyResult<()> {
+        let amount = amount
+            .map(|amount| {
+                Qty::from_f64(amount).ok_or_else(|| {
+                    pyo3::exceptions::PyValueError::new_err(format!("invalid amount: {amount}"))
+                })
+            })
+            .transpose()?;
+        let mut max_bags = self.max_bags.lock().unwrap_or_else(|err| err.into_inner());
+        match amount {
+            Some(amount) => max_bags.insert(asset.to_uppercase(), amount),
+            None => max_bags.remove(&asset.to_uppercase()),
+        };
+        Ok(())
+    }
+    /// Send the orders of found triangles as `order.test`, validating them without execution
+    pub fn set_test_orders(&self, enabled: bool) {
+        self.test_orders.store(enabled, Ordering::Relaxed);
+    }
+    pub fn test_orders(&self) -> bool {
+        self.test_orders.load(Ordering::Relaxed)
+    }
+    /// Trade flow of a symbol over the last seconds, fed when the client was created
+    /// with `trade_tape`
+    pub fn tape(&self, symbol: &str) -> TapeStats {
+        self.tapes.stats(&symbol.to_uppercase())
+    }
+    /// Candles of a symbol from the oldest, the last one still open. Built from the
+    /// book ticker mid prices, so without volume, unless its kline stream feeds them.
+    pub fn candles(&self, symbol: &str) -> Vec<Candle> {
+        self.candles.candles(&symbol.to_uppercase())
+    }
+    /// Only fire triangles whose legs all traded `min_volume` lately without an
+    /// imbalance towards their side above `max_imbalance`, or fire regardless with None
+    pub fn set_flow_filter(&self, min_volume: Option<f64>, max_imbalance: Option<f64>) {
+        let filter = (min_volume.is_some() || max_imbalance.is_some()).then(|| FlowFilter {
+            min_volume: min_volume.unwrap_or(0.),
+            max_imbalance: max_imbalance.unwrap_or(1.),
+        });
+        *self.flow_filter.lock().unwrap_or_else(|err| err.into_inner()) = filter;
+    }
+    /// Current request weight and order count usage per interval
+    pub fn rate_limits(&self) -> Vec<RateLimit> {
+        self.rate_limits.usage()
+    }
+    /// Enable the client side rate limiter with mode "delay" or "reject", or disable it with None
+    pub fn set_rate_limiter(&self, mode: Option<&str>, headroom: Option<f64>) -> PyResult<()> {
+        let mode = match mode {
+            None => {
+                self.rate_limits.set_limiter(None);
+                return Ok(());
+            }
+            Some("delay") => LimitMode::Delay,
+            Some("reject") => LimitMode::Reject,
+            Some(mode) => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "invalid rate limiter mode: {mode}"
+                )))
+            }
+        };
+        let default = RateLimiter::default();
+        self.rate_limits.set_limiter(Some(RateLimiter {
+            mode,
+            headroom: headroom.unwrap_or(default.headroom),
+        }));
+        Ok(())
+    }
+    /// Protect a position with an OCO bracket: a LIMIT_MAKER take profit and a
+    /// STOP_LOSS_LIMIT stop, limited at `stop_limit_price` or the stop price
+    #[allow(clippy::too_many_arguments)]
+    pub fn place_oco<'py>(
+        &mut self,
+        py: Python<'py>,
+        symbol: &str,
+        side: &str,
+        quantity: &str,
+        take_profit: &str,
+        stop_price: &str,
+        stop_limit_price: Option<&str>,
+    ) -> PyResult<&'py PyAny> {
+        let request = OrderListPlaceOcoRequest::bracket(
+            &self.api_key,
+            symbol,
+            parse_side(side)?,
+            parse_decimal(quantity)?,
+            parse_decimal(take_profit)?,
+            parse_decimal(stop_price)?,
+            stop_limit_price.map(parse_decimal).transpose()?,
+        );
+        self.command(py, |sender| Command::PlaceOco(request, sender))
+    }
+    pub fn cancel_order_list<'py>(&mut self, py: Python<'py>, symbol: &str, order_list_id: i64) -> PyResult<&'py PyAny> {
+        let request = OrderListCancelRequest::new(&self.api_key, symbol, order_list_id);
+        self.command(py, |sender| Command::CancelOrderList(request, sender))
+    }
+    pub fn order_list_status<'py>(&mut self, py: Python<'py>, order_list_id: i64) -> PyResult<&'py PyAny> {
+        let request = OrderListStatusRequest::new(&self.api_key, order_list_id);
+        self.command(py, |sender| Command::OrderListStatus(request, sender))
+    }
+    /// Next report of the user data stream, when the client was created with it
+    pub fn get_execution_report<'py>(&self, py: Python<'py>) -> PyResult<&'py PyAny> {
+        let reciever = self.executions_reciever.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let mut reciever = reciever.lock().await;
+            loop {
+                match reciever.recv().await {
+                    Ok(report) => return Ok(report),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        log::warn!("Dropped {skipped} execution reports")
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Err(Error::Disconnected.into()),
+                }
+            }
+        })
+    }
+    /// Triangles among the trading symbols, read from an `exchangeInfo` JSON file at
+    /// `path` or requested from the exchange. Only those quoted in `anchor`, without
+    /// the `excluded` assets and whose legs all have a volume in `volumes` of at least
+    /// `min_volume`, ready to pass to `subscribe`.
+    pub fn discover_triangles<'py>(
+        &mut self,
+        py: Python<'py>,
+        anchor: Option<String>,
+        excluded: Option<Vec<String>>,
+        min_volume: Option<f64>,
+        volumes: Option<std::collections::HashMap<String, f64>>,
+        path: Option<String>,
+    ) -> PyResult<&'py PyAny> {
+        let discovery = Discovery {
+            anchor,
+            excluded: excluded.unwrap_or_default().into_iter().collect(),
+            min_volume: min_volume.unwrap_or(0.),
+            volumes: volumes.unwrap_or_default(),
+        };
+        match path {
+            Some(path) => {
+                let triangles = discovery.triangles(&discovery::load_symbols(path)?);
+                pyo3_asyncio::tokio::future_into_py(py, async move { Ok(triangles) })
+            }
+            None => self.command(py, |sender| Command::DiscoverTriangles(discovery, sender)),
         }
     }
     // pub fn update_tris(&self, map: HashMap<String, HashMap<String, i32>>) {}
@@ -263,28 +936,84 @@ impl Client {
         pyo3_asyncio::tokio::future_into_py(
             py,
             async move {
-                Ok(reciever.lock().await.recv().await)
+                let result = reciever.lock().await.recv().await;
+                Ok(result.transpose()?)
             },
         )
     }
-    pub fn subscribe(&mut self, triangles: Vec<(String, String, String)>) {
+    /// Hand the triangles to the workers. A subscription the server rejects is reported
+    /// through `get_result`, the triangle has to be unsubscribed before retrying it.
+    /// Spread the triangles over the workers, each subscribes to its share in one
+    /// request
+    pub fn subscribe(&mut self, triangles: Vec<(String, String, String)>) -> PyResult<()> {
         // TODO: proper python conversion traits
-        for(base, quote, alt) in triangles {
-            self.subscription_senders[self.next_sender]
-                .try_send(Subscription::Subscribe(Triangle::new(
-                    base,
-                    quote,
-                    alt,
-                )))
-                .expect("couldn't send subscription");
+        let mut batches = vec![Vec::new(); self.subscription_senders.len()];
+        for (base, quote, alt) in triangles {
+            let triangle = Triangle::new(base, quote, alt);
+            if self.assignments.contains_key(&triangle) {
+                continue;
+            }
+            batches[self.next_sender].push(triangle.clone());
+            self.assignments.insert(triangle, self.next_sender);
             self.next_sender = (self.next_sender + 1) % self.subscription_senders.len();
         }
+        self.send_batches(batches, Subscription::Subscribe)
+    }
+    pub fn unsubscribe(&mut self, triangles: Vec<(String, String, String)>) -> PyResult<()> {
+        let mut batches = vec![Vec::new(); self.subscription_senders.len()];
+        for (base, quote, alt) in triangles {
+            let triangle = Triangle::new(base, quote, alt);
+            let Some(worker) = self.assignments.remove(&triangle) else {
+                continue;
+            };
+            batches[worker].push(triangle);
+        }
+        self.send_batches(batches, Subscription::Unsubscribe)
+    }
+}
+
+impl Client {
+    /// Hand each worker its batch of triangles, skipping the empty ones
+    fn send_batches(
+        &self,
+        batches: Vec<Vec<Triangle>>,
+        subscription: fn(Vec<Triangle>) -> Subscription,
+    ) -> PyResult<()> {
+        for (sender, batch) in self.subscription_senders.iter().zip(batches) {
+            if !batch.is_empty() {
+                sender
+                    .send(subscription(batch))
+                    .map_err(|_| Error::Disconnected)?;
+            }
+        }
+        Ok(())
+    }
+    /// Hand a request to the next worker and await its result from Python
+    fn command<'py, T>(
+        &mut self,
+        py: Python<'py>,
+        command: impl FnOnce(oneshot::Sender<Result<T>>) -> Command,
+    ) -> PyResult<&'py PyAny>
+    where
+        T: IntoPy<PyObject> + Send + 'static,
+    {
+        let (sender, reciever) = oneshot::channel();
+        self.command_senders[self.next_command]
+            .send(command(sender))
+            .map_err(|_| Error::Disconnected)?;
+        self.next_command = (self.next_command + 1) % self.command_senders.len();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let result = reciever.await.map_err(|_| Error::Disconnected)?;
+            Ok(result?)
+        })
     }
 }
 
 impl Drop for Client {
     fn drop(&mut self) {
         self.subscription_senders.clear();
+        self.command_senders.clear();
+        self.user_data_shutdown.take();
         for thread in self.threads.drain(..) {
             thread.join().unwrap();
         }
@@ -292,8 +1021,19 @@ impl Drop for Client {
 }
 
 #[pymodule]
-fn arbitrage_processing(_py: Python, m: &PyModule) -> PyResult<()> {
+fn arbitrage_processing(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<Client>()?;
+    m.add_class::<RateLimit>()?;
+    m.add_class::<OrderResponseFull>()?;
+    m.add_class::<Fill>()?;
+    m.add_class::<Balance>()?;
+    m.add_class::<TapeStats>()?;
+    m.add_class::<Candle>()?;
+    m.add_class::<ExecutionReport>()?;
+    m.add_class::<OrderListResponse>()?;
+    m.add_class::<OrderListEntry>()?;
+    m.add_class::<OrderReport>()?;
+    exceptions::register(py, m)?;
 
     Ok(())
 }
@@ -0,0 +1,339 @@
+use std::{
+    collections::VecDeque,
+    num::NonZeroU64,
+    sync::{Arc, Mutex},
+};
+
+use ahash::AHashMap as HashMap;
+use pyo3::pyclass;
+
+use crate::api::{
+    decimal::Decimal,
+    market::Kline,
+    subscription::{KlineUpdate, StreamKline, SubscriptionUpdate},
+};
+
+/// OHLCV of a symbol over one interval
+#[pyclass]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candle {
+    #[pyo3(get)]
+    pub open_time: u64,
+    #[pyo3(get)]
+    pub close_time: u64,
+    #[pyo3(get)]
+    pub open: f64,
+    #[pyo3(get)]
+    pub high: f64,
+    #[pyo3(get)]
+    pub low: f64,
+    #[pyo3(get)]
+    pub close: f64,
+    /// Traded base volume, always 0 for candles built from book prices
+    #[pyo3(get)]
+    pub volume: f64,
+}
+
+impl Candle {
+    fn new(open_time: u64, interval: u64, price: f64, volume: f64) -> Self {
+        Candle {
+            open_time,
+            close_time: open_time + interval - 1,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume,
+        }
+    }
+
+    fn from_decimals(
+        open_time: u64,
+        close_time: u64,
+        [open, high, low, close, volume]: [Decimal; 5],
+    ) -> Self {
+        Candle {
+            open_time,
+            close_time,
+            open: open.to_f64(),
+            high: high.to_f64(),
+            low: low.to_f64(),
+            close: close.to_f64(),
+            volume: volume.to_f64(),
+        }
+    }
+
+    fn from_stream(kline: &StreamKline) -> Self {
+        let StreamKline {
+            open,
+            high,
+            low,
+            close,
+            volume,
+            ..
+        } = *kline;
+        Candle::from_decimals(
+            kline.open_time,
+            kline.close_time,
+            [*open, *high, *low, *close, *volume],
+        )
+    }
+
+    /// Candle of a `klines` response
+    pub fn from_kline(kline: &Kline) -> Self {
+        let Kline {
+            open,
+            high,
+            low,
+            close,
+            volume,
+            ..
+        } = *kline;
+        Candle::from_decimals(
+            kline.open_time,
+            kline.close_time,
+            [*open, *high, *low, *close, *volume],
+        )
+    }
+}
+
+/// Candles of one symbol, the last `capacity` closed ones and the current one
+#[derive(Clone, Debug)]
+pub struct CandleBuilder {
+    /// Length of a candle in milliseconds
+    interval: NonZeroU64,
+    capacity: usize,
+    closed: VecDeque<Candle>,
+    current: Option<Candle>,
+    /// Fed by a kline stream, prices from other sources are ignored
+    from_klines: bool,
+}
+
+impl CandleBuilder {
+    /// Candles of `interval` milliseconds, None if it is 0
+    pub fn new(interval: u64, capacity: usize) -> Option<Self> {
+        Some(Self::with_interval(NonZeroU64::new(interval)?, capacity))
+    }
+
+    fn with_interval(interval: NonZeroU64, capacity: usize) -> Self {
+        CandleBuilder {
+            interval,
+            capacity,
+            closed: VecDeque::with_capacity(capacity),
+            current: None,
+            from_klines: false,
+        }
+    }
+
+    fn close_current(&mut self) {
+        let Some(candle) = self.current.take() else {
+            return;
+        };
+        if self.closed.len() == self.capacity {
+            self.closed.pop_front();
+        }
+        if self.capacity > 0 {
+            self.closed.push_back(candle);
+        }
+    }
+
+    /// Add a price observed at `time`, intervals without any are skipped rather
+    /// than filled in
+    pub fn push(&mut self, time: u64, price: f64, volume: f64) {
+        let open_time = time - time % self.interval.get();
+        match &mut self.current {
+            Some(candle) if candle.open_time == open_time => {
+                candle.high = candle.high.max(price);
+                candle.low = candle.low.min(price);
+                candle.close = price;
+                candle.volume += volume;
+            }
+            // late for a candle already closed
+            Some(candle) if candle.open_time > open_time => {}
+            _ => {
+                self.close_current();
+                self.current = Some(Candle::new(open_time, self.interval.get(), price, volume));
+            }
+        }
+    }
+
+    /// Take the candle of a kline stream update as is, from then on the builder
+    /// only follows the stream
+    pub fn apply_kline(&mut self, kline: &StreamKline) {
+        let candle = Candle::from_stream(kline);
+        if !self.from_klines {
+            // drop the candles built from other prices
+            self.from_klines = true;
+            self.closed.clear();
+            self.current = None;
+        }
+        match &self.current {
+            Some(current) if current.open_time > candle.open_time => return,
+            Some(current) if current.open_time < candle.open_time => self.close_current(),
+            _ => {}
+        }
+        self.current = Some(candle);
+        if kline.is_closed {
+            self.close_current();
+        }
+    }
+
+    pub fn is_from_klines(&self) -> bool {
+        self.from_klines
+    }
+
+    /// Closed candles from the oldest, followed by the current one
+    pub fn candles(&self) -> Vec<Candle> {
+        self.closed
+            .iter()
+            .chain(self.current.as_ref())
+            .cloned()
+            .collect()
+    }
+}
+
+/// Candles of every watched symbol, built from the `bookTicker` mid prices unless a
+/// `kline_<interval>` stream of the same interval feeds them. Clones share the same
+/// candles.
+#[derive(Clone, Debug)]
+pub struct Candles {
+    interval: NonZeroU64,
+    capacity: usize,
+    inner: Arc<Mutex<HashMap<String, CandleBuilder>>>,
+}
+
+impl Candles {
+    /// Candles of `interval` milliseconds, keeping `capacity` closed ones per symbol.
+    /// None if the interval is 0.
+    pub fn new(interval: u64, capacity: usize) -> Option<Self> {
+        Some(Candles {
+            interval: NonZeroU64::new(interval)?,
+            capacity,
+            inner: Default::default(),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, CandleBuilder>> {
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn with_builder(&self, symbol: &str, f: impl FnOnce(&mut CandleBuilder)) {
+        let mut inner = self.lock();
+        match inner.get_mut(symbol) {
+            Some(builder) => f(builder),
+            None => {
+                let mut builder = CandleBuilder::with_interval(self.interval, self.capacity);
+                f(&mut builder);
+                inner.insert(symbol.into(), builder);
+            }
+        }
+    }
+
+    /// Add the mid price of a book ticker observed at `time`
+    pub fn record_book_ticker(&self, update: &SubscriptionUpdate, time: u64) {
+        let (bid, ask) = (
+            update.best_bid_price.to_f64(),
+            update.best_ask_price.to_f64(),
+        );
+        self.with_builder(&update.symbol, |builder| {
+            if !builder.is_from_klines() {
+                builder.push(time, (bid + ask) / 2., 0.);
+            }
+        });
+    }
+
+    pub fn record_kline(&self, update: &KlineUpdate) {
+        if update.kline.interval.millis() != Some(self.interval.get()) {
+            return;
+        }
+        self.with_builder(&update.symbol, |builder| builder.apply_kline(&update.kline));
+    }
+
+    pub fn candles(&self, symbol: &str) -> Vec<Candle> {
+        self.lock()
+            .get(symbol)
+            .map(CandleBuilder::candles)
+            .unwrap_or_default()
+    }
+
+    /// Forget the candles of a symbol no longer watched
+    pub fn remove(&self, symbol: &str) {
+        self.lock().remove(symbol);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::types::KlineInterval;
+
+    fn kline(open_time: u64, close: &str, is_closed: bool) -> StreamKline {
+        StreamKline {
+            open_time,
+            close_time: open_time + 59_999,
+            interval: KlineInterval::M1,
+            first_trade_id: 0,
+            last_trade_id: 0,
+            open: "1.0".parse().unwrap(),
+            close: close.parse().unwrap(),
+            high: "3.0".parse().unwrap(),
+            low: "0.5".parse().unwrap(),
+            volume: "10.0".parse().unwrap(),
+            trades: 1,
+            is_closed,
+            quote_volume: "10.0".parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn prices() {
+        assert!(CandleBuilder::new(0, 2).is_none());
+        assert!(Candles::new(0, 2).is_none());
+
+        let mut builder = CandleBuilder::new(60_000, 2).unwrap();
+        builder.push(60_000, 2., 0.);
+        builder.push(61_000, 3., 0.);
+        builder.push(62_000, 1., 0.);
+        builder.push(125_000, 4., 0.);
+        // late
+        builder.push(119_000, 10., 0.);
+
+        let candles = builder.candles();
+        assert_eq!(candles.len(), 2);
+        assert_eq!(
+            candles[0],
+            Candle {
+                open_time: 60_000,
+                close_time: 119_999,
+                open: 2.,
+                high: 3.,
+                low: 1.,
+                close: 1.,
+                volume: 0.,
+            }
+        );
+        assert_eq!(candles[1].open_time, 120_000);
+        assert_eq!(candles[1].close, 4.);
+
+        builder.push(180_000, 5., 0.);
+        builder.push(240_000, 6., 0.);
+        let candles = builder.candles();
+        assert_eq!(candles.len(), 3);
+        assert_eq!(candles[0].open_time, 120_000);
+    }
+
+    #[test]
+    fn klines() {
+        let mut builder = CandleBuilder::new(60_000, 10).unwrap();
+        builder.push(60_000, 2., 0.);
+        builder.apply_kline(&kline(60_000, "2.0", false));
+        builder.apply_kline(&kline(60_000, "2.5", true));
+        builder.apply_kline(&kline(120_000, "1.5", false));
+
+        let candles = builder.candles();
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[0].close, 2.5);
+        assert_eq!(candles[0].volume, 10.);
+        assert_eq!(candles[1].close, 1.5);
+    }
+}
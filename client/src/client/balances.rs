@@ -0,0 +1,60 @@
+use crate::api::{account::Balance, decimal::Qty};
+use ahash::AHashMap as HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Last known balances of the account, refreshed by every `account.status` response.
+/// Clones share the same cache.
+#[derive(Clone, Debug, Default)]
+pub struct Balances {
+    inner: Arc<Mutex<HashMap<String, Balance>>>,
+}
+
+impl Balances {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Balance>> {
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    pub fn get(&self, asset: &str) -> Option<Balance> {
+        self.lock().get(asset).cloned()
+    }
+
+    /// Free amount of the asset, 0 if unknown
+    pub fn free(&self, asset: &str) -> Qty {
+        self.lock()
+            .get(asset)
+            .map(|balance| balance.free)
+            .unwrap_or_default()
+    }
+
+    /// Whether the balances were loaded at least once
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    pub fn all(&self) -> Vec<Balance> {
+        self.lock().values().cloned().collect()
+    }
+
+    /// Apply the balances that changed, e.g. from an `outboundAccountPosition` event
+    pub fn update(&self, balances: &[Balance]) {
+        let mut inner = self.lock();
+        for balance in balances {
+            inner.insert(balance.asset.clone(), balance.clone());
+        }
+    }
+
+    /// Replace the cache with a full snapshot of the account
+    pub(crate) fn replace(&self, balances: &[Balance]) {
+        let mut inner = self.lock();
+        inner.clear();
+        inner.extend(
+            balances
+                .iter()
+                .map(|balance| (balance.asset.clone(), balance.clone())),
+        );
+    }
+}
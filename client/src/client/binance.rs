@@ -1,26 +1,153 @@
-use crate::api::{utils, ws::WsResponseHeader, BinanceRequest, ProcessedRequest};
+use super::{
+    listen_key::ListenKeyKeepalive, time_sync::TimeSync, Balances, Commissions, Keepalive,
+    RateLimits, ReconnectPolicy, SymbolRules,
+};
+use crate::{
+    api::{
+        account::{
+            AccountCommissionRequest, AccountCommissionResponse, AccountRateLimitsOrdersRequest,
+            AccountStatusRequest, AccountStatusResponse,
+        },
+        general::{
+            EmptyResponse, ExchangeInfoRequest, ExchangeInfoResponse, TimeRequest, TimeResponse,
+        },
+        subscription::{SubscribeRequest, UnsubscribeRequest},
+        types::RateLimit,
+        user_data::{ListenKeyResponse, UserDataStreamStartRequest, UserDataStreamStopRequest},
+        utils,
+        ws::{RequestId, WsResponse, WsResponseHeader},
+        BinanceRequest, ProcessedRequest,
+    },
+    Error, Result,
+};
 use actix_codec::Framed;
-use actix_web::web::Bytes;
+use actix_http::ws::Item;
+use actix_web::web::{Bytes, BytesMut};
 use ahash::AHashMap as HashMap;
 use awc::{
     error::WsProtocolError,
-    ws::{Codec, Frame},
+    ws::{CloseReason, Codec, Frame},
     BoxedSocket,
 };
 use futures::SinkExt;
 use futures_util::StreamExt;
-use std::time::Duration;
-use uuid::Uuid;
+use std::{collections::VecDeque, marker::PhantomData, time::Duration};
+use tokio::time::Instant;
 
 pub type Connection = Framed<BoxedSocket, Codec>;
 
+/// Largest frame accepted by default, an unfiltered `exchangeInfo` response takes
+/// several megabytes
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 32 * 1024 * 1024;
+
+/// Handle to a sent request, resolved into its typed response by
+/// `BinanceClient::response_to`
+#[derive(Debug)]
+pub struct ResponseHandle<T: ?Sized> {
+    id: RequestId,
+    marker: PhantomData<T>,
+}
+
+impl<T: ?Sized> ResponseHandle<T> {
+    pub fn id(&self) -> RequestId {
+        self.id
+    }
+}
+
+impl<T: ?Sized> Clone for ResponseHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for ResponseHandle<T> {}
+
+#[derive(Debug)]
+pub enum Event {
+    /// A text frame, with the id and method of the request it answers if any
+    Response {
+        id: Option<RequestId>,
+        method: Option<&'static str>,
+        bytes: Bytes,
+    },
+    /// A binary frame
+    Binary(Bytes),
+    /// A pong, with the round trip time if it answers one of our pings
+    Pong { latency: Option<Duration> },
+    /// The server closed the connection or the stream ended
+    Disconnected { reason: Option<CloseReason> },
+    /// The connection was re-established, requests in flight were lost
+    Reconnected {
+        lost: Vec<(RequestId, &'static str)>,
+    },
+    /// The connection dropped for good without a reconnect policy, requests in flight
+    /// were lost
+    Lost {
+        lost: Vec<(RequestId, &'static str)>,
+    },
+    /// No response arrived before the deadline of the request, it is no longer tracked
+    TimedOut { id: RequestId, method: &'static str },
+    /// The server clock was measured, offset and round trip time in milliseconds
+    TimeSynced { offset: i64, rtt: u64 },
+    /// Measuring the server clock failed, the last offset stays until the next attempt
+    TimeSyncFailed { error: Error },
+    /// Keeping the listen key alive failed, the user data stream has to be restarted
+    ListenKeyExpired { listen_key: String },
+}
+
+/// Request waiting for its response
+#[derive(Debug)]
+struct InFlight {
+    method: &'static str,
+    deadline: Option<Instant>,
+}
+
+/// What woke `next_inner` up
+enum Wake {
+    Frame(Option<std::result::Result<Frame, WsProtocolError>>),
+    Timer,
+}
+
+/// Message being reassembled from continuation frames
+enum Continuation {
+    Text(BytesMut),
+    Binary(BytesMut),
+}
+
 pub struct BinanceClient {
     pending: usize,
     prepared: usize,
     connection: Option<Connection>,
     url: String,
-    requests: HashMap<Uuid, &'static str>,
-    // buffer: HashMap<Uuid, (Option<&'static str>, Option<Result<Frame, WsProtocolError>>)>,
+    timeout: Duration,
+    max_frame_size: usize,
+    reconnect: Option<ReconnectPolicy>,
+    /// Failed reconnect attempts in a row, kept across cancelled `next` calls
+    reconnect_attempt: u32,
+    /// When the next reconnect attempt is due
+    reconnect_at: Option<Instant>,
+    /// A reconnect is under way, possibly connected but not yet replayed
+    reconnecting: bool,
+    rate_limits: RateLimits,
+    balances: Balances,
+    commissions: Commissions,
+    symbol_rules: SymbolRules,
+    time_sync: Option<TimeSync>,
+    keepalive: Option<Keepalive>,
+    listen_key: Option<ListenKeyKeepalive>,
+    last_frame: Instant,
+    next_ping: Instant,
+    latency: Option<Duration>,
+    request_timeout: Option<Duration>,
+    requests: HashMap<RequestId, InFlight>,
+    /// Requests in flight when the connection dropped, reported once it is settled
+    lost: Vec<(RequestId, &'static str)>,
+    streams: Vec<String>,
+    /// Streams of the subscriptions awaiting their ack, replayed only once acked
+    subscribing: HashMap<RequestId, Vec<String>>,
+    buffer: VecDeque<Event>,
+    pings: VecDeque<u64>,
+    continuation: Option<Continuation>,
 }
 
 impl BinanceClient {
@@ -30,33 +157,346 @@ impl BinanceClient {
             prepared: 0,
             connection: None,
             url,
+            timeout: Duration::from_secs(5),
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            reconnect: None,
+            reconnect_attempt: 0,
+            reconnect_at: None,
+            reconnecting: false,
+            rate_limits: RateLimits::new(),
+            balances: Balances::new(),
+            commissions: Commissions::new(),
+            symbol_rules: SymbolRules::new(),
+            time_sync: None,
+            keepalive: None,
+            listen_key: None,
+            last_frame: Instant::now(),
+            next_ping: Instant::now(),
+            latency: None,
+            request_timeout: None,
             requests: HashMap::default(),
-            // buffer: HashMap::default(),
+            lost: Vec::new(),
+            streams: Vec::new(),
+            subscribing: HashMap::default(),
+            buffer: VecDeque::new(),
+            pings: VecDeque::new(),
+            continuation: None,
         }
     }
 
     // /// Set whether to operate in bounded mode, that is, to expect unsolicited messages
     // pub fn set_bounded(&mut self, bounded: bool)
 
-    fn get_connection(&mut self) -> &mut Connection {
-        self.connection.as_mut().expect("No connection")
+    /// Set the policy used to re-establish the connection once the server drops it,
+    /// `None` disables reconnecting
+    pub fn set_reconnect_policy(&mut self, policy: Option<ReconnectPolicy>) {
+        self.reconnect = policy;
+    }
+
+    /// Rate limit usage reported by the server
+    pub fn rate_limits(&self) -> &RateLimits {
+        &self.rate_limits
+    }
+
+    /// Share a rate limit tracker, e.g. between clients connecting from the same IP
+    pub fn set_rate_limits(&mut self, rate_limits: RateLimits) {
+        self.rate_limits = rate_limits;
+    }
+
+    /// Balances cached from `account.status` responses
+    pub fn balances(&self) -> &Balances {
+        &self.balances
+    }
+
+    /// Share a balance cache, e.g. between clients trading on the same account
+    pub fn set_balances(&mut self, balances: Balances) {
+        self.balances = balances;
+    }
+
+    /// Commission rates cached from `account.commission` responses
+    pub fn commissions(&self) -> &Commissions {
+        &self.commissions
+    }
+
+    /// Share a commission cache, e.g. between clients trading on the same account
+    pub fn set_commissions(&mut self, commissions: Commissions) {
+        self.commissions = commissions;
     }
 
-    pub async fn connect(&mut self, timeout: Duration) {
+    /// Trading rules cached from `exchangeInfo` responses
+    pub fn symbol_rules(&self) -> &SymbolRules {
+        &self.symbol_rules
+    }
+
+    /// Share a trading rules registry, e.g. between clients trading the same symbols
+    pub fn set_symbol_rules(&mut self, symbol_rules: SymbolRules) {
+        self.symbol_rules = symbol_rules;
+    }
+
+    /// Measure the server clock on connect and then at the given interval, the offset is
+    /// applied to the timestamps of new requests. `None` disables it.
+    pub fn set_time_sync(&mut self, interval: Option<Duration>) {
+        self.time_sync = interval.map(TimeSync::new);
+    }
+
+    /// Ping the server periodically and declare the connection dead when no frame
+    /// arrives in time. `None` disables it.
+    pub fn set_keepalive(&mut self, keepalive: Option<Keepalive>) {
+        self.keepalive = keepalive;
+        self.reset_keepalive();
+    }
+
+    /// Start a user data stream and keep its listen key alive, pinging it at the given
+    /// interval. Returns the listen key to connect to the stream with.
+    pub async fn start_user_data_stream(
+        &mut self,
+        api_key: &str,
+        interval: Duration,
+    ) -> Result<String> {
+        let ListenKeyResponse { listen_key } = self
+            .request(UserDataStreamStartRequest::new(api_key))
+            .await?;
+        log::info!("User data stream started");
+        self.listen_key = Some(ListenKeyKeepalive::new(
+            api_key,
+            listen_key.clone(),
+            interval,
+        ));
+
+        Ok(listen_key)
+    }
+
+    /// Close the user data stream started by `start_user_data_stream`
+    pub async fn stop_user_data_stream(&mut self) -> Result<()> {
+        let Some(keepalive) = self.listen_key.take() else {
+            return Ok(());
+        };
+        let request = UserDataStreamStopRequest::new(keepalive.api_key(), keepalive.listen_key());
+        self.request(request).await?;
+        log::info!("User data stream stopped");
+
+        Ok(())
+    }
+
+    /// Listen key of the user data stream kept alive by this client
+    pub fn listen_key(&self) -> Option<&str> {
+        self.listen_key.as_ref().map(ListenKeyKeepalive::listen_key)
+    }
+
+    /// Set the largest frame accepted from the server, in bytes. Applies from the
+    /// next connection.
+    pub fn set_max_frame_size(&mut self, max_frame_size: usize) {
+        self.max_frame_size = max_frame_size;
+    }
+
+    /// Set how long requests wait for their response before `Error::Timeout`,
+    /// `None` waits forever
+    pub fn set_request_timeout(&mut self, timeout: Option<Duration>) {
+        self.request_timeout = timeout;
+    }
+
+    /// Override the deadline of a single request
+    pub fn set_timeout<T>(&mut self, handle: &ResponseHandle<T>, timeout: Duration) {
+        if let Some(in_flight) = self.requests.get_mut(&handle.id) {
+            in_flight.deadline = Some(Instant::now() + timeout);
+        }
+    }
+
+    /// Stop waiting for the response to a request, a late response is handed out
+    /// as unsolicited. Returns whether the request was in flight.
+    pub fn cancel(&mut self, id: RequestId) -> bool {
+        self.buffer.retain(
+            |event| !matches!(event, Event::Response { id: Some(other), .. } if *other == id),
+        );
+        match self.requests.remove(&id) {
+            Some(in_flight) => {
+                log::info!("Cancel {} request with id {id}", in_flight.method);
+                self.forget_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Uncount a request that will not be answered
+    fn forget_one(&mut self) {
+        if self.pending > 0 {
+            self.pending -= 1;
+        } else {
+            self.prepared = self.prepared.saturating_sub(1);
+        }
+    }
+
+    fn track(&mut self, id: RequestId, method: &'static str) {
+        let deadline = self.request_timeout.map(|timeout| Instant::now() + timeout);
+        self.requests.insert(id, InFlight { method, deadline });
+    }
+
+    /// Round trip time of the last answered ping
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+
+    fn reset_keepalive(&mut self) {
+        self.last_frame = Instant::now();
+        if let Some(keepalive) = &self.keepalive {
+            self.next_ping = self.last_frame + keepalive.interval;
+        }
+    }
+
+    /// Wait for the client side limiter to allow a request, then count it
+    async fn throttle(&mut self, weight: u32, orders: u32) -> Result<()> {
+        if let Some(wait) = self.rate_limits.check(weight, orders)? {
+            log::warn!("Rate limited, waiting {wait:?}");
+            tokio::time::sleep(wait).await;
+        }
+        self.rate_limits.record(weight, orders);
+
+        Ok(())
+    }
+
+    fn get_connection(&mut self) -> Result<&mut Connection> {
+        self.connection.as_mut().ok_or(Error::NotConnected)
+    }
+
+    pub async fn connect(&mut self, timeout: Duration) -> Result<()> {
+        self.timeout = timeout;
+        self.connect_inner().await?;
+
+        if self.time_sync.is_some() {
+            self.sync_time().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Measure the server clock and wait for the result, buffering other events. Gives
+    /// up after the request timeout, or the connect one without it.
+    async fn sync_time(&mut self) -> Result<()> {
+        self.start_time_sync().await?;
+        let timeout = self.request_timeout.unwrap_or(self.timeout);
+        let synced = async {
+            loop {
+                match self.next_inner().await? {
+                    Some(Event::TimeSynced { .. }) => return Ok(()),
+                    Some(Event::TimeSyncFailed { error }) => return Err(error),
+                    Some(event) => self.buffer.push_back(event),
+                    None => return Err(Error::Disconnected),
+                }
+            }
+        };
+        tokio::time::timeout(timeout, synced)
+            .await
+            .map_err(|_| Error::Timeout)?
+    }
+
+    async fn start_time_sync(&mut self) -> Result<()> {
+        let id = self.send_internal(TimeRequest::new().preprocess()?).await?;
+        if let Some(time_sync) = &mut self.time_sync {
+            time_sync.start(id);
+        }
+
+        Ok(())
+    }
+
+    /// Send a request the client answers itself, it is neither tracked nor counted
+    /// as pending
+    async fn send_internal<T>(&mut self, request: ProcessedRequest<T>) -> Result<RequestId>
+    where
+        T: BinanceRequest,
+    {
+        self.throttle(request.weight, request.orders).await?;
+        let connection = self.get_connection()?;
+        connection
+            .send(awc::ws::Message::Text(request.text.into()))
+            .await?;
+
+        log::debug!(
+            "Send internal {} request with id {}",
+            request.method,
+            request.id
+        );
+
+        Ok(request.id)
+    }
+
+    async fn connect_inner(&mut self) -> Result<()> {
         let client = awc::Client::builder()
-            .timeout(timeout)
+            .timeout(self.timeout)
             .max_http_version(awc::http::Version::HTTP_11)
             .finish();
 
-        let (_resp, connection) = client.ws(&self.url).connect().await.unwrap();
+        let (_resp, connection) = client
+            .ws(&self.url)
+            .max_frame_size(self.max_frame_size)
+            .connect()
+            .await?;
         self.connection = Some(connection);
+        self.reset_keepalive();
 
         log::info!("Client connected");
+
+        Ok(())
+    }
+
+    /// Re-establish the connection according to the reconnect policy and replay
+    /// the active subscriptions. Returns the requests that were lost in flight,
+    /// or `None` if the policy gave up.
+    ///
+    /// Cancel safe: the attempt count, its deadline and the pending replay are kept
+    /// on the client, a dropped call is resumed by the next one.
+    async fn reconnect(&mut self) -> Option<Vec<(RequestId, &'static str)>> {
+        let policy = self.reconnect.clone()?;
+        self.reconnecting = true;
+
+        while self.connection.is_none() {
+            let attempt = self.reconnect_attempt;
+            if !policy.allows(attempt) {
+                log::error!("Giving up reconnecting after {attempt} attempts");
+                self.reconnect_attempt = 0;
+                self.reconnect_at = None;
+                self.reconnecting = false;
+                return None;
+            }
+
+            let deadline = *self.reconnect_at.get_or_insert_with(|| {
+                let backoff = policy.backoff(attempt);
+                log::info!("Reconnecting in {backoff:?} (attempt {})", attempt + 1);
+                Instant::now() + backoff
+            });
+            tokio::time::sleep_until(deadline).await;
+
+            // an attempt cut short counts as failed
+            self.reconnect_at = None;
+            self.reconnect_attempt += 1;
+            match self.connect_inner().await {
+                Ok(()) => self.reconnect_attempt = 0,
+                Err(err) => log::warn!("Reconnect attempt {} failed: {err}", attempt + 1),
+            }
+        }
+
+        // replayed again if cut short, subscribing twice is harmless
+        if !self.streams.is_empty() {
+            let request = SubscribeRequest::new(self.streams.clone());
+            let replayed = match request.preprocess() {
+                Ok(request) => self.send(request).await.map(|_| ()),
+                Err(err) => {
+                    log::error!("Failed to prepare subscription replay: {err}");
+                    Ok(())
+                }
+            };
+            if let Err(err) = replayed {
+                log::error!("Failed to replay subscriptions: {err}");
+            }
+        }
+        self.reconnecting = false;
+
+        Some(std::mem::take(&mut self.lost))
     }
 
-    pub async fn disconnect(self) -> Result<(), WsProtocolError> {
+    pub async fn disconnect(self) -> Result<()> {
         if let Some(mut connection) = self.connection {
-            return connection.close().await;
+            connection.close().await?;
         }
 
         log::info!("Client disconnected");
@@ -64,62 +504,125 @@ impl BinanceClient {
         Ok(())
     }
 
-    pub async fn ping(&mut self) -> Result<(), WsProtocolError> {
+    pub async fn ping(&mut self) -> Result<()> {
         let timestamp = utils::timestamp();
         let ping = Bytes::copy_from_slice(timestamp.to_string().as_bytes());
-        let connection = self.get_connection();
-        connection.send(awc::ws::Message::Ping(ping)).await
+        let connection = self.get_connection()?;
+        connection.send(awc::ws::Message::Ping(ping)).await?;
+        self.pings.push_back(timestamp);
+
+        Ok(())
     }
 
-    pub async fn pong(&mut self, bytes: &[u8]) -> Result<(), WsProtocolError> {
+    pub async fn pong(&mut self, bytes: &[u8]) -> Result<()> {
         let pong = Bytes::copy_from_slice(bytes);
-        let connection = self.get_connection();
-        connection.send(awc::ws::Message::Pong(pong)).await
+        let connection = self.get_connection()?;
+        connection.send(awc::ws::Message::Pong(pong)).await?;
+
+        Ok(())
     }
 
-    pub async fn send<T>(&mut self, request: ProcessedRequest<T>) -> Result<Uuid, WsProtocolError>
+    pub async fn send<T>(&mut self, request: ProcessedRequest<T>) -> Result<ResponseHandle<T>>
     where
         T: BinanceRequest,
     {
-        let connection = self.get_connection();
+        self.throttle(request.weight, request.orders).await?;
+        let connection = self.get_connection()?;
         connection
             .send(awc::ws::Message::Text(request.text.into()))
             .await?;
 
         log::info!(
             "Send {} request with id {} ({} prepared)",
-            T::METHOD,
+            request.method,
             request.id,
             self.prepared
         );
 
-        self.requests.insert(request.id, T::METHOD);
+        self.track(request.id, request.method);
         self.pending += 1 + self.prepared;
         self.prepared = 0;
 
-        Ok(request.id)
+        Ok(ResponseHandle {
+            id: request.id,
+            marker: PhantomData,
+        })
     }
 
-    pub async fn feed<T>(&mut self, request: ProcessedRequest<T>) -> Result<Uuid, WsProtocolError>
+    pub async fn feed<T>(&mut self, request: ProcessedRequest<T>) -> Result<ResponseHandle<T>>
     where
         T: BinanceRequest,
     {
-        let connection = self.get_connection();
+        self.throttle(request.weight, request.orders).await?;
+        let connection = self.get_connection()?;
         connection
             .feed(awc::ws::Message::Text(request.text.into()))
             .await?;
 
-        log::info!("Prepare {} request with id {}", T::METHOD, request.id);
+        log::info!("Prepare {} request with id {}", request.method, request.id);
 
-        self.requests.insert(request.id, T::METHOD);
+        self.track(request.id, request.method);
         self.prepared += 1;
 
-        Ok(request.id)
+        Ok(ResponseHandle {
+            id: request.id,
+            marker: PhantomData,
+        })
     }
 
-    pub async fn flush(&mut self) -> Result<(), WsProtocolError> {
-        let connection = self.get_connection();
-        let result = connection.flush().await;
+    /// Subscribe to the given streams, remembering them once acked so they are
+    /// replayed after a reconnect
+    pub async fn subscribe(
+        &mut self,
+        request: SubscribeRequest,
+    ) -> Result<ResponseHandle<SubscribeRequest>> {
+        let streams = request.symbols.clone();
+        let request = request.preprocess()?;
+        let handle = self.send(request).await?;
+        self.subscribing.insert(handle.id, streams);
+
+        Ok(handle)
+    }
+
+    /// Remember the streams of an acked subscription, a rejected one is dropped
+    fn settle_subscription(&mut self, id: RequestId, bytes: &[u8]) {
+        let Some(streams) = self.subscribing.remove(&id) else {
+            return;
+        };
+        if let Err(err) = SubscribeRequest::decode(bytes).and_then(Error::from_response) {
+            log::warn!("Subscription {id} rejected, not replaying it: {err}");
+            return;
+        }
+        for stream in streams {
+            if !self.streams.contains(&stream) {
+                self.streams.push(stream);
+            }
+        }
+    }
+
+    /// Unsubscribe from the given streams, they are no longer replayed after a reconnect
+    pub async fn unsubscribe(
+        &mut self,
+        request: UnsubscribeRequest,
+    ) -> Result<ResponseHandle<UnsubscribeRequest>> {
+        self.streams
+            .retain(|stream| !request.symbols.contains(stream));
+        for streams in self.subscribing.values_mut() {
+            streams.retain(|stream| !request.symbols.contains(stream));
+        }
+
+        let request = request.preprocess()?;
+        self.send(request).await
+    }
+
+    /// Streams subscribed to through `subscribe`
+    pub fn streams(&self) -> &[String] {
+        &self.streams
+    }
+
+    pub async fn flush(&mut self) -> Result<()> {
+        let connection = self.get_connection()?;
+        let result = connection.flush().await.map_err(Error::from);
 
         log::info!("Flush {} requests", self.prepared);
         self.pending += self.prepared;
@@ -136,83 +639,458 @@ impl BinanceClient {
         self.pending != 0
     }
 
-    async fn next_inner(
-        &mut self,
-    ) -> Result<Option<(Option<Uuid>, Option<&'static str>, Bytes)>, WsProtocolError>
-// (
-    //     Option<Uuid>,
-    //     (Option<&'static str>, Option<Result<Bytes, WsProtocolError>>),
-    // )
-    {
+    async fn next_inner(&mut self) -> Result<Option<Event>> {
         loop {
-            let connection = self.get_connection();
-            let response = connection.next().await;
-            match response {
-                Some(Ok(Frame::Ping(bytes))) => {
-                    let result = self.pong(&bytes).await;
-                    if let Err(err) = result {
-                        return Err(err);
+            let timer = self.next_timer();
+            if self.connection.is_none() || self.reconnecting {
+                // the previous connection dropped, reconnect if the policy allows it
+                if let Some(lost) = self.reconnect().await {
+                    if !lost.is_empty() {
+                        log::warn!("Lost {} requests in flight", lost.len());
                     }
-                    log::info!("Pong({:?})", bytes);
+                    return Ok(Some(Event::Reconnected { lost }));
                 }
-                Some(Ok(Frame::Text(response_bytes))) => {
-                    let response = String::from_utf8(response_bytes.to_vec()).unwrap();
-                    let id_method: Option<(Uuid, Option<&str>)> =
-                        serde_json::from_str::<WsResponseHeader>(&response)
-                            .ok()
-                            .map(|header| (header.id, self.requests.remove(&header.id)));
-                    let id = id_method.map(|p| p.0);
-                    let method = id_method.and_then(|p| p.1);
-
-                    match (id, method) {
-                        (Some(id), Some(method)) => {
-                            log::info!("Received {method} response with id {id}")
-                        }
-                        (Some(id), None) => log::info!("Received response with id {id}"),
-                        (None, None) => log::info!("Recieved response"),
-                        (None, Some(_)) => unreachable!(),
-                    };
-
-                    // only decrement for messages with an id
-                    if let Some(_) = id {
-                        self.pending -= 1;
+                if !self.lost.is_empty() {
+                    let lost = std::mem::take(&mut self.lost);
+                    log::warn!("Lost {} requests in flight", lost.len());
+                    return Ok(Some(Event::Lost { lost }));
+                }
+                return Ok(None);
+            }
+            let connection = self.connection.as_mut().ok_or(Error::NotConnected)?;
+
+            let wake = tokio::select! {
+                frame = connection.next() => Wake::Frame(frame),
+                _ = tokio::time::sleep_until(timer.unwrap_or_else(Instant::now)),
+                    if timer.is_some() => Wake::Timer,
+            };
+            let frame = match wake {
+                Wake::Timer => {
+                    if let Some(event) = self.on_timer().await? {
+                        return Ok(Some(event));
                     }
-                    return Ok(Some((id, method, response_bytes)));
+                    continue;
+                }
+                Wake::Frame(frame) => frame,
+            };
+            self.last_frame = Instant::now();
+
+            let frame = match frame {
+                Some(Ok(frame)) => frame,
+                Some(Err(err)) => {
+                    log::error!("Protocol error: {err}");
+                    self.drop_connection();
+                    return Err(err.into());
                 }
                 None => {
                     log::warn!("Server disconnected");
-                    self.pending = 0;
-                    return Ok(None);
+                    self.drop_connection();
+                    return Ok(Some(Event::Disconnected { reason: None }));
+                }
+            };
+
+            match frame {
+                Frame::Ping(bytes) => {
+                    self.pong(&bytes).await?;
+                    log::info!("Pong({:?})", bytes);
+                }
+                Frame::Pong(bytes) => {
+                    let latency = self.pong_latency(&bytes);
+                    self.latency = latency.or(self.latency);
+                    log::info!("Received pong after {latency:?}");
+                    return Ok(Some(Event::Pong { latency }));
+                }
+                Frame::Text(bytes) => {
+                    let event = self.text_event(bytes);
+                    if let Some(event) = self.intercept(event) {
+                        return Ok(Some(event));
+                    }
+                }
+                Frame::Binary(bytes) => return Ok(Some(Event::Binary(bytes))),
+                Frame::Continuation(item) => {
+                    if let Some(event) = self.continue_message(item)? {
+                        return Ok(Some(event));
+                    }
+                }
+                Frame::Close(reason) => {
+                    log::warn!("Server closed the connection: {reason:?}");
+                    // answering the close is best effort, the connection is dropped anyway
+                    if let Ok(connection) = self.get_connection() {
+                        let _ = connection.close().await;
+                    }
+                    self.drop_connection();
+                    return Ok(Some(Event::Disconnected { reason }));
                 }
-                // TODO: this is bad error handling
-                _ => unimplemented!("{response:?}"),
             }
         }
     }
 
-    pub async fn next(
-        &mut self,
-    ) -> Result<Option<(Option<Uuid>, Option<&'static str>, Bytes)>, WsProtocolError> {
+    /// Earliest instant at which a periodic task is due
+    fn next_timer(&self) -> Option<Instant> {
+        let keepalive = self
+            .keepalive
+            .as_ref()
+            .map(|keepalive| self.next_ping.min(self.last_frame + keepalive.timeout));
+        let time_sync = self.time_sync.as_ref().map(TimeSync::next);
+        let listen_key = self.listen_key.as_ref().map(ListenKeyKeepalive::next);
+        let deadline = self
+            .requests
+            .values()
+            .filter_map(|in_flight| in_flight.deadline)
+            .min();
+
+        [keepalive, time_sync, listen_key, deadline]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    /// Run the periodic tasks that are due, returning the disconnect event if the
+    /// connection went silent for too long
+    async fn on_timer(&mut self) -> Result<Option<Event>> {
+        let now = Instant::now();
+        if let Some(keepalive) = self.keepalive.clone() {
+            if self.last_frame + keepalive.timeout <= now {
+                log::warn!(
+                    "No frame received for {:?}, dropping the connection",
+                    now - self.last_frame
+                );
+                self.drop_connection();
+                return Ok(Some(Event::Disconnected { reason: None }));
+            }
+            if self.next_ping <= now {
+                self.next_ping = now + keepalive.interval;
+                self.ping().await?;
+            }
+        }
+        if matches!(&self.time_sync, Some(time_sync) if time_sync.next() <= now) {
+            self.start_time_sync().await?;
+        }
+        if let Some(keepalive) = self.listen_key.as_ref().filter(|key| key.next() <= now) {
+            let request = keepalive.request().preprocess()?;
+            let id = self.send_internal(request).await?;
+            if let Some(keepalive) = &mut self.listen_key {
+                keepalive.start(id);
+            }
+        }
+
+        let expired = self
+            .requests
+            .iter()
+            .filter(
+                |(_, in_flight)| matches!(in_flight.deadline, Some(deadline) if deadline <= now),
+            )
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        let mut timed_out = VecDeque::with_capacity(expired.len());
+        for id in expired {
+            if let Some(in_flight) = self.requests.remove(&id) {
+                log::warn!("{} request with id {id} timed out", in_flight.method);
+                self.forget_one();
+                timed_out.push_back(Event::TimedOut {
+                    id,
+                    method: in_flight.method,
+                });
+            }
+        }
+        let first = timed_out.pop_front();
+        self.buffer.extend(timed_out);
+
+        Ok(first)
+    }
+
+    /// Handle the responses to internal requests and refresh the caches from account
+    /// and exchange responses, passing other events through
+    fn intercept(&mut self, event: Event) -> Option<Event> {
+        let Event::Response {
+            id: Some(id),
+            method,
+            bytes,
+        } = &event
+        else {
+            return Some(event);
+        };
+        // by id, `cancel` or a timeout may have untracked the request
+        self.settle_subscription(*id, bytes);
+        if let Some(method) = method {
+            self.observe(method, bytes);
+            return Some(event);
+        }
+
+        if let Some(keepalive) = self.listen_key.as_mut().filter(|key| key.is_pending(*id)) {
+            keepalive.finish();
+            let response = serde_json::from_slice::<WsResponse<EmptyResponse>>(bytes)
+                .map_err(Error::from)
+                .and_then(Error::from_response);
+            return match response {
+                Ok(_) => {
+                    log::debug!("Listen key kept alive");
+                    None
+                }
+                Err(err) => {
+                    log::error!("Listen key keepalive failed: {err}");
+                    let keepalive = self.listen_key.take()?;
+                    Some(Event::ListenKeyExpired {
+                        listen_key: keepalive.listen_key().into(),
+                    })
+                }
+            };
+        }
+
+        match &mut self.time_sync {
+            Some(time_sync) if time_sync.is_pending(*id) => {
+                let response = serde_json::from_slice::<WsResponse<TimeResponse>>(bytes)
+                    .map_err(Error::from)
+                    .and_then(Error::from_response);
+                match response {
+                    Ok(TimeResponse { server_time }) => {
+                        let (offset, rtt) = time_sync.finish(server_time)?;
+                        log::info!("Server clock offset {offset}ms (rtt {rtt}ms)");
+                        Some(Event::TimeSynced { offset, rtt })
+                    }
+                    Err(error) => {
+                        log::error!("Time sync failed: {error}");
+                        Some(Event::TimeSyncFailed { error })
+                    }
+                }
+            }
+            _ => Some(event),
+        }
+    }
+
+    /// Update the balances, commissions, order counts and trading rules from the results of account
+    /// and exchange requests
+    fn observe(&self, method: &str, bytes: &[u8]) {
+        match method {
+            AccountStatusRequest::METHOD => {
+                let response = serde_json::from_slice::<WsResponse<AccountStatusResponse>>(bytes);
+                if let Ok(WsResponse {
+                    result: Some(account),
+                    ..
+                }) = response
+                {
+                    self.balances.replace(&account.balances);
+                }
+            }
+            AccountCommissionRequest::METHOD => {
+                let response =
+                    serde_json::from_slice::<WsResponse<AccountCommissionResponse>>(bytes);
+                if let Ok(WsResponse {
+                    result: Some(commission),
+                    ..
+                }) = response
+                {
+                    self.commissions.update(&commission);
+                }
+            }
+            AccountRateLimitsOrdersRequest::METHOD => {
+                let response = serde_json::from_slice::<WsResponse<Vec<RateLimit>>>(bytes);
+                if let Ok(WsResponse {
+                    result: Some(limits),
+                    ..
+                }) = response
+                {
+                    self.rate_limits.update(&limits);
+                }
+            }
+            ExchangeInfoRequest::METHOD => {
+                let response = serde_json::from_slice::<WsResponse<ExchangeInfoResponse>>(bytes);
+                if let Ok(WsResponse {
+                    result: Some(info), ..
+                }) = response
+                {
+                    self.symbol_rules.load(&info.symbols);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Forget the connection, the requests in flight will never be answered
+    fn drop_connection(&mut self) {
+        self.connection = None;
+        self.pending = 0;
+        self.prepared = 0;
+        self.pings.clear();
+        self.continuation = None;
+        if let Some(time_sync) = &mut self.time_sync {
+            time_sync.reset();
+        }
+        self.lost.extend(
+            self.requests
+                .drain()
+                .map(|(id, in_flight)| (id, in_flight.method)),
+        );
+        // unacked subscriptions are lost with the rest
+        self.subscribing.clear();
+    }
+
+    fn text_event(&mut self, bytes: Bytes) -> Event {
+        let header = serde_json::from_slice::<WsResponseHeader>(&bytes).ok();
+        if let Some(header) = &header {
+            self.rate_limits.update(&header.rate_limits);
+            let retry_after = header.error.as_ref().and_then(|error| error.data.as_ref());
+            if let Some(retry_after) = retry_after.and_then(|data| data.retry_after) {
+                log::warn!("Banned until {retry_after}");
+                self.rate_limits.ban(retry_after);
+            }
+        }
+        let id = header.map(|header| header.id);
+        let method = id
+            .and_then(|id| self.requests.remove(&id))
+            .map(|in_flight| in_flight.method);
+
+        match (id, method) {
+            (Some(id), Some(method)) => {
+                log::info!("Received {method} response with id {id}")
+            }
+            (Some(id), None) => log::info!("Received response with id {id}"),
+            (None, _) => log::info!("Recieved response"),
+        };
+
+        // only decrement for responses to tracked requests
+        if method.is_some() {
+            self.pending = self.pending.saturating_sub(1);
+        }
+
+        Event::Response { id, method, bytes }
+    }
+
+    /// Round trip time of the ping answered by this pong, forgetting older pings
+    fn pong_latency(&mut self, bytes: &[u8]) -> Option<Duration> {
+        let timestamp = std::str::from_utf8(bytes).ok()?.parse::<u64>().ok()?;
+        let position = self.pings.iter().position(|&ping| ping == timestamp)?;
+        self.pings.drain(..=position);
+
+        Some(Duration::from_millis(
+            utils::timestamp().saturating_sub(timestamp),
+        ))
+    }
+
+    /// Accumulate a continuation frame, returning the event once the message is complete
+    fn continue_message(&mut self, item: Item) -> Result<Option<Event>> {
+        match (self.continuation.take(), item) {
+            (None, Item::FirstText(bytes)) => {
+                self.continuation = Some(Continuation::Text(BytesMut::from(&bytes[..])));
+                Ok(None)
+            }
+            (None, Item::FirstBinary(bytes)) => {
+                self.continuation = Some(Continuation::Binary(BytesMut::from(&bytes[..])));
+                Ok(None)
+            }
+            (Some(mut continuation), Item::Continue(bytes)) => {
+                match &mut continuation {
+                    Continuation::Text(buffer) | Continuation::Binary(buffer) => {
+                        buffer.extend_from_slice(&bytes)
+                    }
+                }
+                self.continuation = Some(continuation);
+                Ok(None)
+            }
+            (Some(Continuation::Text(mut buffer)), Item::Last(bytes)) => {
+                buffer.extend_from_slice(&bytes);
+                let event = self.text_event(buffer.freeze());
+                Ok(self.intercept(event))
+            }
+            (Some(Continuation::Binary(mut buffer)), Item::Last(bytes)) => {
+                buffer.extend_from_slice(&bytes);
+                Ok(Some(Event::Binary(buffer.freeze())))
+            }
+            (Some(_), Item::FirstText(_) | Item::FirstBinary(_)) => {
+                Err(WsProtocolError::ContinuationStarted.into())
+            }
+            (None, Item::Continue(_) | Item::Last(_)) => {
+                Err(WsProtocolError::ContinuationNotStarted.into())
+            }
+        }
+    }
+
+    /// Next event, starting with the ones buffered while waiting in `response_to`
+    pub async fn next(&mut self) -> Result<Option<Event>> {
+        if let Some(event) = self.buffer.pop_front() {
+            return Ok(Some(event));
+        }
         self.next_inner().await
     }
 
-    // pub async fn response_to(
-    //     &mut self,
-    //     id: Uuid,
-    // ) -> (Option<&'static str>, Option<Result<Frame, WsProtocolError>>) {
-    //     if let Some(resp) = self.buffer.remove(&id) {
-    //         resp
-    //     } else {
-    //         loop {
-    //             let (id2, resp) = self.next_inner().await;
-    //             if let Some(id2) = id2 {
-    //                 if id == id2 {
-    //                     return resp;
-    //                 } else {
-    //                     self.buffer.insert(id2, resp);
-    //                 }
-    //             }
-    //         }
-    //     }
-    // }
+    /// Wait for the response to the given request. Frames that arrive in the meantime
+    /// are buffered and handed out by later `response_to` or `next` calls, pongs and
+    /// clock measurements are dropped.
+    pub async fn response_to<T>(
+        &mut self,
+        handle: &ResponseHandle<T>,
+    ) -> Result<WsResponse<T::Response>>
+    where
+        T: BinanceRequest,
+    {
+        let id = handle.id;
+        let buffered = self.buffer.iter().position(|event| match event {
+            Event::Response {
+                id: Some(other), ..
+            }
+            | Event::TimedOut { id: other, .. } => *other == id,
+            _ => false,
+        });
+        let bytes = match buffered.and_then(|position| self.buffer.remove(position)) {
+            Some(Event::Response { bytes, .. }) => bytes,
+            Some(_) => return Err(Error::Timeout),
+            None if self.lost.iter().any(|(other, _)| *other == id) => return Err(Error::Lost(id)),
+            None if !self.requests.contains_key(&id) => return Err(Error::UnknownRequest(id)),
+            None => loop {
+                match self.next_inner().await? {
+                    Some(Event::Response {
+                        id: Some(other),
+                        bytes,
+                        ..
+                    }) if other == id => break bytes,
+                    Some(Event::TimedOut { id: other, .. }) if other == id => {
+                        return Err(Error::Timeout)
+                    }
+                    Some(Event::Reconnected { lost }) => {
+                        let was_lost = lost.iter().any(|(other, _)| *other == id);
+                        self.buffer.push_back(Event::Reconnected { lost });
+                        if was_lost {
+                            return Err(Error::Lost(id));
+                        }
+                    }
+                    Some(Event::Lost { lost }) => {
+                        let was_lost = lost.iter().any(|(other, _)| *other == id);
+                        self.buffer.push_back(Event::Lost { lost });
+                        if was_lost {
+                            return Err(Error::Lost(id));
+                        }
+                    }
+                    // informational, the client already recorded them
+                    Some(
+                        Event::Pong { .. }
+                        | Event::TimeSynced { .. }
+                        | Event::TimeSyncFailed { .. },
+                    ) => {}
+                    Some(event) => self.buffer.push_back(event),
+                    None => return Err(Error::Disconnected),
+                }
+            },
+        };
+
+        T::decode(&bytes)
+    }
+
+    /// Wait for the response to the given request and decode its result, a failed
+    /// response is turned into `Error::Api`
+    pub async fn result_of<T>(&mut self, handle: &ResponseHandle<T>) -> Result<T::Response>
+    where
+        T: BinanceRequest,
+    {
+        let response = self.response_to(handle).await?;
+        Error::from_response(response)
+    }
+
+    /// Send a request and wait for its decoded result
+    pub async fn request<T>(&mut self, request: T) -> Result<T::Response>
+    where
+        T: BinanceRequest,
+    {
+        let handle = self.send(request.preprocess()?).await?;
+        self.result_of(&handle).await
+    }
 }
@@ -0,0 +1,43 @@
+use crate::api::{account::AccountCommissionResponse, decimal::Decimal};
+use ahash::AHashMap as HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Standard taker rate, assumed for symbols whose rate is not loaded yet
+pub const DEFAULT_TAKER_COMMISSION: Decimal = Decimal::from_units(100_000);
+
+/// Taker commission rates per symbol, refreshed by every `account.commission`
+/// response. Clones share the same cache.
+#[derive(Clone, Debug, Default)]
+pub struct Commissions {
+    inner: Arc<Mutex<HashMap<String, Decimal>>>,
+}
+
+impl Commissions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Decimal>> {
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Fraction of a market order paid as commission, None until loaded. The BNB
+    /// discount is left out as it only applies while holding BNB.
+    pub fn taker(&self, symbol: &str) -> Option<Decimal> {
+        self.lock().get(symbol).copied()
+    }
+
+    /// Taker rate of the symbol, the standard one until loaded
+    pub fn taker_or_default(&self, symbol: &str) -> Decimal {
+        self.taker(symbol).unwrap_or(DEFAULT_TAKER_COMMISSION)
+    }
+
+    pub fn contains(&self, symbol: &str) -> bool {
+        self.lock().contains_key(symbol)
+    }
+
+    pub fn update(&self, response: &AccountCommissionResponse) {
+        let taker = response.standard_commission.taker + response.tax_commission.taker;
+        self.lock().insert(response.symbol.clone(), taker);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::api::secret_key::SecretKey;
+use crate::{api::secret_key::SecretKey, Result};
 use serde::{Deserialize, Serialize};
 use std::{fs::File, io::BufReader, path::Path};
 
@@ -15,7 +15,7 @@ impl Config {
         Self::default()
     }
 
-    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, anyhow::Error> {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
         let file = File::open(path)?;
         let reader = BufReader::new(file);
         let config = serde_json::from_reader(reader)?;
@@ -0,0 +1,64 @@
+use crate::{api::subscription::StreamEnvelope, Result};
+use ahash::AHashMap as HashMap;
+use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
+
+type Handler = Box<dyn FnMut(&str, &RawValue) -> Result<()>>;
+
+/// Routes the frames of a combined stream connection to typed handlers, by full
+/// stream name (`btcusdt@bookTicker`) or by stream type (`bookTicker`)
+#[derive(Default)]
+pub struct StreamDispatcher {
+    handlers: HashMap<String, Handler>,
+}
+
+impl StreamDispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode the data of the matching streams as `T` and hand it to `handler` along
+    /// with the stream name, replacing any handler registered for `route`
+    pub fn on<T, F>(&mut self, route: &str, mut handler: F) -> &mut Self
+    where
+        T: DeserializeOwned,
+        F: FnMut(&str, T) + 'static,
+    {
+        self.handlers.insert(
+            route.into(),
+            Box::new(move |stream, data| {
+                handler(stream, serde_json::from_str(data.get())?);
+                Ok(())
+            }),
+        );
+        self
+    }
+
+    /// Stop routing the given stream name or type
+    pub fn remove(&mut self, route: &str) -> bool {
+        self.handlers.remove(route).is_some()
+    }
+
+    /// Hand a frame to its handler. Returns false for frames that are not envelopes,
+    /// like stream request acks, and for streams without a handler.
+    pub fn dispatch(&mut self, bytes: &[u8]) -> Result<bool> {
+        let Ok(envelope) = serde_json::from_slice::<StreamEnvelope<&RawValue>>(bytes) else {
+            return Ok(false);
+        };
+        let StreamEnvelope { stream, data } = envelope;
+        let handler = match self.handlers.get_mut(&stream) {
+            Some(handler) => handler,
+            None => match self.handlers.get_mut(stream_type(&stream)) {
+                Some(handler) => handler,
+                None => return Ok(false),
+            },
+        };
+        handler(&stream, data)?;
+        Ok(true)
+    }
+}
+
+/// `depth` for `btcusdt@depth@100ms`
+fn stream_type(stream: &str) -> &str {
+    stream.split('@').nth(1).unwrap_or(stream)
+}
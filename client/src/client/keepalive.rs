@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+/// Pings sent by `BinanceClient` to detect half-open connections
+#[derive(Clone, Debug)]
+pub struct Keepalive {
+    /// Time between two pings
+    pub interval: Duration,
+    /// Time without any frame after which the connection is considered dead
+    pub timeout: Duration,
+}
+
+impl Default for Keepalive {
+    fn default() -> Self {
+        Keepalive {
+            interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl Keepalive {
+    pub fn new(interval: Duration, timeout: Duration) -> Self {
+        Keepalive { interval, timeout }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::api::{user_data::UserDataStreamPingRequest, ws::RequestId};
+use tokio::time::{Duration, Instant};
+
+/// Periodic `userDataStream.ping` keeping a listen key valid, the server expires it
+/// after 60 minutes without one
+#[derive(Debug)]
+pub(crate) struct ListenKeyKeepalive {
+    api_key: String,
+    listen_key: String,
+    interval: Duration,
+    next: Instant,
+    pending: Option<RequestId>,
+}
+
+impl ListenKeyKeepalive {
+    pub(crate) fn new(api_key: &str, listen_key: String, interval: Duration) -> Self {
+        ListenKeyKeepalive {
+            api_key: api_key.into(),
+            listen_key,
+            interval,
+            next: Instant::now() + interval,
+            pending: None,
+        }
+    }
+
+    pub(crate) fn listen_key(&self) -> &str {
+        &self.listen_key
+    }
+
+    pub(crate) fn api_key(&self) -> &str {
+        &self.api_key
+    }
+
+    pub(crate) fn next(&self) -> Instant {
+        self.next
+    }
+
+    pub(crate) fn request(&self) -> UserDataStreamPingRequest {
+        UserDataStreamPingRequest::new(&self.api_key, &self.listen_key)
+    }
+
+    pub(crate) fn start(&mut self, id: RequestId) {
+        self.pending = Some(id);
+        self.next = Instant::now() + self.interval;
+    }
+
+    pub(crate) fn is_pending(&self, id: RequestId) -> bool {
+        self.pending == Some(id)
+    }
+
+    pub(crate) fn finish(&mut self) {
+        self.pending = None;
+    }
+}
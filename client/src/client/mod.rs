@@ -1,4 +1,20 @@
+mod balances;
 mod binance;
+mod commissions;
 pub mod config;
+mod dispatcher;
+mod keepalive;
+mod listen_key;
+mod rate_limit;
+mod reconnect;
+mod symbol_rules;
+mod time_sync;
 
+pub use balances::*;
 pub use binance::*;
+pub use commissions::*;
+pub use dispatcher::*;
+pub use keepalive::*;
+pub use rate_limit::*;
+pub use reconnect::*;
+pub use symbol_rules::*;
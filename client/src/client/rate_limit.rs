@@ -0,0 +1,268 @@
+use crate::{
+    api::{
+        types::{RateLimit, RateLimitType},
+        utils,
+    },
+    Error, Result,
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// What the limiter does with a request that would exceed a limit
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LimitMode {
+    /// Wait until the window resets
+    Delay,
+    /// Fail with `Error::RateLimited`
+    Reject,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct RateLimiter {
+    pub mode: LimitMode,
+    /// Fraction of each limit the client allows itself to use
+    pub headroom: f64,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        RateLimiter {
+            mode: LimitMode::Delay,
+            headroom: 0.9,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// Last known usage with the start of the window it was reported in
+    usage: Vec<(RateLimit, u64)>,
+    banned_until: Option<u64>,
+    limiter: Option<RateLimiter>,
+}
+
+/// Live view of the rate limits reported by the server. Clones share the same view,
+/// so clients connecting from the same IP can share one tracker.
+#[derive(Clone, Debug, Default)]
+pub struct RateLimits {
+    inner: Arc<Mutex<Inner>>,
+}
+
+fn window_start(limit: &RateLimit, now: u64) -> u64 {
+    let window = limit.window().max(1);
+    now - now % window
+}
+
+fn used(limit: &RateLimit, weight: u32, orders: u32) -> u32 {
+    match limit.rate_limit_type {
+        RateLimitType::RequestWeight => weight,
+        RateLimitType::Orders => orders,
+        RateLimitType::RawRequests => 1,
+    }
+}
+
+impl RateLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Enable the client side limiter, `None` only tracks usage
+    pub fn set_limiter(&self, limiter: Option<RateLimiter>) {
+        self.lock().limiter = limiter;
+    }
+
+    /// Current usage, with the counts of elapsed windows reset
+    pub fn usage(&self) -> Vec<RateLimit> {
+        let now = utils::server_timestamp();
+        self.lock()
+            .usage
+            .iter()
+            .map(|(limit, start)| {
+                let mut limit = limit.clone();
+                if window_start(&limit, now) != *start {
+                    limit.count = 0;
+                }
+                limit
+            })
+            .collect()
+    }
+
+    /// Take the usage reported in a response
+    pub(crate) fn update(&self, limits: &[RateLimit]) {
+        let now = utils::server_timestamp();
+        let mut inner = self.lock();
+        for limit in limits {
+            let start = window_start(limit, now);
+            let known = inner.usage.iter_mut().find(|(known, _)| {
+                known.rate_limit_type == limit.rate_limit_type
+                    && known.interval == limit.interval
+                    && known.interval_num == limit.interval_num
+            });
+            match known {
+                Some(known) => *known = (limit.clone(), start),
+                None => inner.usage.push((limit.clone(), start)),
+            }
+        }
+    }
+
+    /// Remember a ban reported along a 418 or 429 status
+    pub(crate) fn ban(&self, retry_after: u64) {
+        let mut inner = self.lock();
+        inner.banned_until = inner.banned_until.max(Some(retry_after));
+    }
+
+    /// Count a request that is about to be sent
+    pub(crate) fn record(&self, weight: u32, orders: u32) {
+        let now = utils::server_timestamp();
+        for (limit, start) in self.lock().usage.iter_mut() {
+            let used = used(limit, weight, orders);
+            let current = window_start(limit, now);
+            if current != *start {
+                *start = current;
+                limit.count = 0;
+            }
+            limit.count += used;
+        }
+    }
+
+    /// How long a request has to wait before it can be sent, or an error if the
+    /// limiter rejects it
+    pub(crate) fn check(&self, weight: u32, orders: u32) -> Result<Option<Duration>> {
+        let now = utils::server_timestamp();
+        let inner = self.lock();
+        let Some(limiter) = inner.limiter else {
+            return Ok(None);
+        };
+
+        let mut until = inner.banned_until.filter(|&until| until > now);
+        for (limit, start) in &inner.usage {
+            let used = used(limit, weight, orders);
+            let count = if window_start(limit, now) == *start {
+                limit.count
+            } else {
+                0
+            };
+            let allowed = (limit.limit as f64 * limiter.headroom) as u32;
+            if used > 0 && count + used > allowed {
+                until = until.max(Some(window_start(limit, now) + limit.window()));
+            }
+        }
+
+        match until {
+            None => Ok(None),
+            Some(until) => {
+                let wait = Duration::from_millis(until.saturating_sub(now));
+                match limiter.mode {
+                    LimitMode::Delay => Ok(Some(wait)),
+                    LimitMode::Reject => Err(Error::RateLimited { retry_after: wait }),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::types::RateLimitInterval;
+
+    fn limit(rate_limit_type: RateLimitType, limit: u32, count: u32) -> RateLimit {
+        RateLimit {
+            rate_limit_type,
+            interval: RateLimitInterval::Day,
+            interval_num: 1,
+            limit,
+            count,
+        }
+    }
+
+    fn limits(mode: LimitMode) -> RateLimits {
+        let limits = RateLimits::new();
+        limits.update(&[
+            limit(RateLimitType::RequestWeight, 100, 80),
+            limit(RateLimitType::Orders, 10, 0),
+        ]);
+        limits.set_limiter(Some(RateLimiter {
+            mode,
+            headroom: 0.9,
+        }));
+        limits
+    }
+
+    fn counts(limits: &RateLimits) -> Vec<u32> {
+        limits.usage().iter().map(|limit| limit.count).collect()
+    }
+
+    #[test]
+    fn record() {
+        let limits = limits(LimitMode::Reject);
+        limits.record(2, 1);
+        limits.record(3, 0);
+        assert_eq!(counts(&limits), [85, 1]);
+
+        // a newer report replaces the local count
+        limits.update(&[limit(RateLimitType::RequestWeight, 100, 40)]);
+        assert_eq!(counts(&limits), [40, 1]);
+    }
+
+    #[test]
+    fn check() {
+        let limits = limits(LimitMode::Reject);
+        assert!(matches!(limits.check(10, 1), Ok(None)));
+        assert!(matches!(
+            limits.check(11, 0),
+            Err(Error::RateLimited { retry_after }) if retry_after <= Duration::from_secs(86_400)
+        ));
+
+        // orders only count against the order limit
+        limits.record(0, 9);
+        assert!(matches!(limits.check(1, 0), Ok(None)));
+        assert!(limits.check(1, 1).is_err());
+
+        let delayed = self::limits(LimitMode::Delay);
+        assert!(matches!(delayed.check(11, 0), Ok(Some(_))));
+
+        // without a limiter usage is only tracked
+        limits.set_limiter(None);
+        assert!(matches!(limits.check(1_000, 100), Ok(None)));
+    }
+
+    #[test]
+    fn window_reset() {
+        let limits = limits(LimitMode::Reject);
+        assert!(limits.check(20, 0).is_err());
+
+        // move the reported usage to the previous window
+        for (limit, start) in limits.lock().usage.iter_mut() {
+            *start -= limit.window();
+        }
+        assert_eq!(counts(&limits), [0, 0]);
+        assert!(matches!(limits.check(20, 0), Ok(None)));
+
+        limits.record(20, 0);
+        assert_eq!(counts(&limits), [20, 0]);
+    }
+
+    #[test]
+    fn ban() {
+        let limits = limits(LimitMode::Delay);
+        let now = utils::server_timestamp();
+        limits.ban(now + 60_000);
+        // an earlier retry time does not shorten the ban
+        limits.ban(now + 1_000);
+        match limits.check(1, 0) {
+            Ok(Some(wait)) => assert!(wait > Duration::from_secs(50)),
+            other => panic!("expected a delay, got {other:?}"),
+        }
+
+        let limits = self::limits(LimitMode::Reject);
+        limits.ban(now.saturating_sub(1_000));
+        assert!(matches!(limits.check(1, 0), Ok(None)));
+    }
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+/// Policy used by `BinanceClient` to re-establish a dropped connection
+#[derive(Clone, Debug)]
+pub struct ReconnectPolicy {
+    /// Maximum amount of consecutive attempts, `None` retries forever
+    pub max_attempts: Option<u32>,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    /// Fraction of the backoff that is randomized, between 0 and 1
+    pub jitter: f64,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            max_attempts: Some(10),
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.,
+            jitter: 0.2,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether another attempt is allowed after `attempt` failed ones
+    pub fn allows(&self, attempt: u32) -> bool {
+        self.max_attempts.is_none_or(|max| attempt < max)
+    }
+
+    /// Delay to wait before the given (zero based) attempt
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let base = self.initial_backoff.as_secs_f64() * self.multiplier.powi(attempt as i32);
+        let base = base.min(self.max_backoff.as_secs_f64());
+        let jitter = self.jitter.clamp(0., 1.);
+        let factor = 1. - jitter + 2. * jitter * rand::random::<f64>();
+
+        Duration::from_secs_f64(base * factor)
+    }
+}
@@ -0,0 +1,357 @@
+use crate::{
+    api::{
+        decimal::{Decimal, Price, Qty},
+        general::{SymbolFilter, SymbolInfo},
+        order::OrderRequest,
+        types::{OrderType, Side},
+    },
+    Error, Result,
+};
+use ahash::AHashMap as HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Bounds and increment of a price or quantity, zero ones are not enforced
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Step {
+    pub min: Decimal,
+    pub max: Decimal,
+    /// Without trailing zeros, values rounded to it are shown with its decimals
+    pub step: Decimal,
+}
+
+impl Step {
+    fn parse(min: &str, max: &str, step: &str) -> Option<Self> {
+        Some(Step {
+            min: min.parse().ok()?,
+            max: max.parse().ok()?,
+            step: step.parse::<Decimal>().ok()?.normalize(),
+        })
+    }
+
+    /// Round to a multiple of the step, down or up
+    pub fn round(&self, value: Decimal, up: bool) -> Decimal {
+        value.round_to(self.step, up)
+    }
+
+    /// Why the value is out of bounds, if it is
+    fn check(&self, value: Decimal) -> Option<String> {
+        if value < self.min {
+            Some(format!(
+                "{value} below the minimum of {}",
+                self.min.normalize()
+            ))
+        } else if self.max.is_positive() && value > self.max {
+            Some(format!(
+                "{value} above the maximum of {}",
+                self.max.normalize()
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Bounds of the price times quantity of an order
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Notional {
+    pub min: Decimal,
+    /// 0 when unbounded
+    pub max: Decimal,
+    pub min_on_market: bool,
+    pub max_on_market: bool,
+}
+
+/// Trading rules of a symbol, parsed from its `exchangeInfo` filters
+#[derive(Clone, Debug, PartialEq)]
+pub struct SymbolRule {
+    pub symbol: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub trading: bool,
+    /// PRICE_FILTER
+    pub price: Option<Step>,
+    /// LOT_SIZE
+    pub lot: Option<Step>,
+    /// MARKET_LOT_SIZE, the step of market orders if it has one
+    pub market_lot: Option<Step>,
+    /// NOTIONAL or the older MIN_NOTIONAL
+    pub notional: Option<Notional>,
+}
+
+impl SymbolRule {
+    pub fn new(info: &SymbolInfo) -> Self {
+        let mut rule = SymbolRule {
+            symbol: info.symbol.clone(),
+            base_asset: info.base_asset.clone(),
+            quote_asset: info.quote_asset.clone(),
+            trading: info.status == "TRADING",
+            price: None,
+            lot: None,
+            market_lot: None,
+            notional: None,
+        };
+        for filter in &info.filters {
+            match filter {
+                SymbolFilter::PriceFilter {
+                    min_price,
+                    max_price,
+                    tick_size,
+                } => rule.price = Step::parse(min_price, max_price, tick_size),
+                SymbolFilter::LotSize {
+                    min_qty,
+                    max_qty,
+                    step_size,
+                } => rule.lot = Step::parse(min_qty, max_qty, step_size),
+                SymbolFilter::MarketLotSize {
+                    min_qty,
+                    max_qty,
+                    step_size,
+                } => rule.market_lot = Step::parse(min_qty, max_qty, step_size),
+                SymbolFilter::MinNotional {
+                    min_notional,
+                    apply_to_market,
+                    ..
+                } => {
+                    rule.notional = min_notional.parse().ok().map(|min| Notional {
+                        min,
+                        max: Decimal::ZERO,
+                        min_on_market: *apply_to_market,
+                        max_on_market: false,
+                    })
+                }
+                SymbolFilter::Notional {
+                    min_notional,
+                    apply_min_to_market,
+                    max_notional,
+                    apply_max_to_market,
+                    ..
+                } => {
+                    rule.notional = match (min_notional.parse(), max_notional.parse()) {
+                        (Ok(min), Ok(max)) => Some(Notional {
+                            min,
+                            max,
+                            min_on_market: *apply_min_to_market,
+                            max_on_market: *apply_max_to_market,
+                        }),
+                        _ => None,
+                    }
+                }
+                SymbolFilter::Other => {}
+            }
+        }
+        rule
+    }
+
+    fn error(&self, filter: &'static str, reason: String) -> Error {
+        Error::Filter {
+            symbol: self.symbol.clone(),
+            filter,
+            reason,
+        }
+    }
+
+    /// Round the quantity down to the lot step and the price to the tick, away from
+    /// the market, then check them against the bounds. Market orders have no price,
+    /// their notional is checked at `reference_price` if given.
+    pub fn conform<R>(
+        &self,
+        order: &mut OrderRequest<R>,
+        reference_price: Option<Price>,
+    ) -> Result<()> {
+        let market = matches!(order.order_type, OrderType::Market);
+        let buy = matches!(order.side, Side::Buy);
+        if !self.trading {
+            return Err(self.error("status", "symbol is not trading".into()));
+        }
+
+        if let (Some(value), Some(rule)) = (order.price, &self.price) {
+            let value = rule.round(*value, !buy);
+            if let Some(reason) = rule.check(value) {
+                return Err(self.error("PRICE_FILTER", reason));
+            }
+            order.price = Some(Price(value));
+        }
+        let price = order.price.or(reference_price);
+
+        let mut notional = None;
+        if let Some(mut value) = order.quantity {
+            let lot = match self.market_lot {
+                Some(lot) if market && lot.step.is_positive() => Some(("MARKET_LOT_SIZE", lot)),
+                _ => self.lot.map(|lot| ("LOT_SIZE", lot)),
+            };
+            if let Some((filter, lot)) = lot {
+                let rounded = lot.round(*value, false);
+                if !rounded.is_positive() {
+                    return Err(self.error(filter, format!("{value} rounds down to nothing")));
+                }
+                if let Some(reason) = lot.check(rounded) {
+                    return Err(self.error(filter, reason));
+                }
+                value = Qty(rounded);
+                order.quantity = Some(value);
+            }
+            notional = price.and_then(|price| price.checked_mul(*value));
+        } else if let Some(quote_qty) = order.quote_order_qty {
+            notional = Some(*quote_qty);
+        }
+
+        if let (Some(rule), Some(notional)) = (&self.notional, notional) {
+            let notional = notional.normalize();
+            if (!market || rule.min_on_market) && notional < rule.min {
+                let reason = format!(
+                    "notional {notional} below the minimum of {}",
+                    rule.min.normalize()
+                );
+                return Err(self.error("NOTIONAL", reason));
+            }
+            if (!market || rule.max_on_market) && rule.max.is_positive() && notional > rule.max {
+                let reason = format!(
+                    "notional {notional} above the maximum of {}",
+                    rule.max.normalize()
+                );
+                return Err(self.error("NOTIONAL", reason));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Trading rules of the symbols, loaded from every `exchangeInfo` response. Clones
+/// share the same registry.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolRules {
+    inner: Arc<Mutex<HashMap<String, SymbolRule>>>,
+}
+
+impl SymbolRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, SymbolRule>> {
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<SymbolRule> {
+        self.lock().get(symbol).cloned()
+    }
+
+    pub fn contains(&self, symbol: &str) -> bool {
+        self.lock().contains_key(symbol)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    pub fn all(&self) -> Vec<SymbolRule> {
+        self.lock().values().cloned().collect()
+    }
+
+    /// Add or replace the rules of the given symbols
+    pub fn load(&self, symbols: &[SymbolInfo]) {
+        let mut inner = self.lock();
+        for info in symbols {
+            inner.insert(info.symbol.clone(), SymbolRule::new(info));
+        }
+    }
+
+    /// Conform an order to the rules of its symbol, see `SymbolRule::conform`
+    pub fn conform<R>(
+        &self,
+        order: &mut OrderRequest<R>,
+        reference_price: Option<Price>,
+    ) -> Result<()> {
+        let rule = self
+            .get(&order.symbol)
+            .ok_or_else(|| Error::UnknownSymbol(order.symbol.clone()))?;
+        rule.conform(order, reference_price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fmt, str::FromStr};
+
+    fn info() -> SymbolInfo {
+        SymbolInfo {
+            symbol: "BTCUSDT".into(),
+            status: "TRADING".into(),
+            base_asset: "BTC".into(),
+            base_asset_precision: 8,
+            quote_asset: "USDT".into(),
+            quote_asset_precision: 8,
+            order_types: vec![OrderType::Limit, OrderType::Market],
+            is_spot_trading_allowed: true,
+            filters: vec![
+                SymbolFilter::PriceFilter {
+                    min_price: "0.01000000".into(),
+                    max_price: "1000000.00000000".into(),
+                    tick_size: "0.01000000".into(),
+                },
+                SymbolFilter::LotSize {
+                    min_qty: "0.00001000".into(),
+                    max_qty: "9000.00000000".into(),
+                    step_size: "0.00001000".into(),
+                },
+                SymbolFilter::Notional {
+                    min_notional: "5.00000000".into(),
+                    apply_min_to_market: true,
+                    max_notional: "9000000.00000000".into(),
+                    apply_max_to_market: false,
+                    avg_price_mins: 5,
+                },
+            ],
+            permissions: vec!["SPOT".into()],
+        }
+    }
+
+    fn decimal<T: FromStr>(text: &str) -> T
+    where
+        T::Err: fmt::Debug,
+    {
+        text.parse().unwrap()
+    }
+
+    fn order(side: Side, order_type: OrderType, quantity: &str) -> OrderRequest {
+        OrderRequest::new("", "BTCUSDT", side, order_type, Some(decimal(quantity)))
+    }
+
+    #[test]
+    fn conform() {
+        let rule = SymbolRule::new(&info());
+
+        let mut buy = order(Side::Buy, OrderType::Limit, "0.12345678");
+        buy.price = Some(decimal("30000.129"));
+        rule.conform(&mut buy, None).unwrap();
+        assert_eq!(buy.quantity.unwrap().to_string(), "0.12345");
+        assert_eq!(buy.price.unwrap().to_string(), "30000.12");
+
+        let mut sell = order(Side::Sell, OrderType::Limit, "0.1");
+        sell.price = Some(decimal("30000.121"));
+        rule.conform(&mut sell, None).unwrap();
+        assert_eq!(sell.quantity.unwrap().to_string(), "0.10000");
+        assert_eq!(sell.price.unwrap().to_string(), "30000.13");
+
+        let mut dust = order(Side::Sell, OrderType::Market, "0.000009");
+        assert!(matches!(
+            rule.conform(&mut dust, Some(decimal("30000"))),
+            Err(Error::Filter {
+                filter: "LOT_SIZE",
+                ..
+            })
+        ));
+
+        let mut small = order(Side::Buy, OrderType::Market, "0.0001");
+        assert!(matches!(
+            rule.conform(&mut small, Some(decimal("30000"))),
+            Err(Error::Filter {
+                filter: "NOTIONAL",
+                ..
+            })
+        ));
+        // without a reference price the notional is left to the exchange
+        rule.conform(&mut small, None).unwrap();
+    }
+}
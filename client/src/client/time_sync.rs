@@ -0,0 +1,53 @@
+use crate::api::{utils, ws::RequestId};
+use tokio::time::{Duration, Instant};
+
+/// Periodic estimation of the server clock offset through the `time` method
+#[derive(Debug)]
+pub(crate) struct TimeSync {
+    interval: Duration,
+    next: Instant,
+    /// Request in flight with the local time it was sent at
+    pending: Option<(RequestId, u64)>,
+}
+
+impl TimeSync {
+    pub(crate) fn new(interval: Duration) -> Self {
+        TimeSync {
+            interval,
+            next: Instant::now(),
+            pending: None,
+        }
+    }
+
+    /// When the next measurement is due, a lost one is retried after an interval
+    pub(crate) fn next(&self) -> Instant {
+        self.next
+    }
+
+    /// Sync as soon as possible, e.g. after a reconnect
+    pub(crate) fn reset(&mut self) {
+        self.next = Instant::now();
+        self.pending = None;
+    }
+
+    pub(crate) fn start(&mut self, id: RequestId) {
+        self.pending = Some((id, utils::timestamp()));
+        self.next = Instant::now() + self.interval;
+    }
+
+    pub(crate) fn is_pending(&self, id: RequestId) -> bool {
+        matches!(self.pending, Some((pending, _)) if pending == id)
+    }
+
+    /// Apply the server time from the response, returning the offset and round trip time
+    /// in milliseconds. The server is assumed to have answered halfway through the round trip.
+    pub(crate) fn finish(&mut self, server_time: u64) -> Option<(i64, u64)> {
+        let (_, sent) = self.pending.take()?;
+        let received = utils::timestamp();
+        let rtt = received.saturating_sub(sent);
+        let offset = server_time as i64 - (sent + rtt / 2) as i64;
+        utils::set_clock(offset, rtt);
+
+        Some((offset, rtt))
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::BufReader,
+    path::Path,
+};
+
+use crate::{
+    api::general::{ExchangeInfoResponse, SymbolInfo},
+    triangles::Triangle,
+    Result,
+};
+
+/// Finds the triangles tradable among a set of symbols. A triangle trades the quote
+/// for the base, the base for the alt and the alt back for the quote, so it takes the
+/// `<base><quote>`, `<alt><base>` and `<alt><quote>` symbols to be trading.
+#[derive(Clone, Debug, Default)]
+pub struct Discovery {
+    /// Only the triangles starting and ending in this asset, their quote
+    pub anchor: Option<String>,
+    /// Assets no leg may trade
+    pub excluded: HashSet<String>,
+    /// Least volume of every leg, checked against `volumes` when positive
+    pub min_volume: f64,
+    /// Volume per symbol, e.g. the 24h quote volume. Symbols missing from it fail a
+    /// positive `min_volume`.
+    pub volumes: HashMap<String, f64>,
+}
+
+impl Discovery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn accepts(&self, info: &SymbolInfo) -> bool {
+        info.status == "TRADING"
+            && info.is_spot_trading_allowed
+            && !self.excluded.contains(&info.base_asset)
+            && !self.excluded.contains(&info.quote_asset)
+            && (self.min_volume <= 0.
+                || self
+                    .volumes
+                    .get(&info.symbol)
+                    .is_some_and(|volume| *volume >= self.min_volume))
+    }
+
+    /// Every triangle among the symbols passing the filters, sorted by quote, base
+    /// and alt
+    pub fn triangles(&self, symbols: &[SymbolInfo]) -> Vec<Triangle> {
+        let symbols = symbols
+            .iter()
+            .filter(|info| self.accepts(info))
+            .collect::<Vec<_>>();
+        let pairs = symbols
+            .iter()
+            .map(|info| (info.base_asset.as_str(), info.quote_asset.as_str()))
+            .collect::<HashSet<_>>();
+        // assets tradable against each quote asset
+        let mut bases = HashMap::<&str, Vec<&str>>::new();
+        for (base, quote) in &pairs {
+            bases.entry(quote).or_default().push(base);
+        }
+
+        let mut triangles = Vec::new();
+        for &(base, quote) in &pairs {
+            if self.anchor.as_ref().is_some_and(|anchor| anchor != quote) {
+                continue;
+            }
+            for &alt in bases.get(base).into_iter().flatten() {
+                if alt != quote && pairs.contains(&(alt, quote)) {
+                    triangles.push(Triangle::new(base.into(), quote.into(), alt.into()));
+                }
+            }
+        }
+        triangles.sort_by(|a, b| (&a.quote, &a.base, &a.alt).cmp(&(&b.quote, &b.base, &b.alt)));
+        triangles
+    }
+}
+
+/// Symbols of an `exchangeInfo` response saved to a JSON file
+pub fn load_symbols<P: AsRef<Path>>(path: P) -> Result<Vec<SymbolInfo>> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let info: ExchangeInfoResponse = serde_json::from_reader(reader)?;
+
+    Ok(info.symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(base: &str, quote: &str) -> SymbolInfo {
+        SymbolInfo {
+            symbol: format!("{base}{quote}"),
+            status: "TRADING".into(),
+            base_asset: base.into(),
+            base_asset_precision: 8,
+            quote_asset: quote.into(),
+            quote_asset_precision: 8,
+            order_types: Vec::new(),
+            is_spot_trading_allowed: true,
+            filters: Vec::new(),
+            permissions: vec!["SPOT".into()],
+        }
+    }
+
+    fn names(triangles: &[Triangle]) -> Vec<(&str, &str, &str)> {
+        triangles
+            .iter()
+            .map(|tri| (tri.base.as_str(), tri.quote.as_str(), tri.alt.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn triangles() {
+        let mut halted = info("DOGE", "BTC");
+        halted.status = "BREAK".into();
+        let symbols = [
+            info("BTC", "USDT"),
+            info("ETH", "USDT"),
+            info("ETH", "BTC"),
+            info("ADA", "BTC"),
+            info("ADA", "USDT"),
+            info("ADA", "ETH"),
+            info("DOGE", "USDT"),
+            halted,
+        ];
+
+        let mut discovery = Discovery::new();
+        assert_eq!(
+            names(&discovery.triangles(&symbols)),
+            [
+                ("ETH", "BTC", "ADA"),
+                ("BTC", "USDT", "ADA"),
+                ("BTC", "USDT", "ETH"),
+                ("ETH", "USDT", "ADA"),
+            ]
+        );
+
+        discovery.anchor = Some("USDT".into());
+        discovery.excluded.insert("ADA".into());
+        assert_eq!(
+            names(&discovery.triangles(&symbols)),
+            [("BTC", "USDT", "ETH")]
+        );
+
+        discovery.excluded.clear();
+        discovery.min_volume = 1_000.;
+        discovery.volumes = [("BTCUSDT", 5_000.), ("ADABTC", 2_000.), ("ADAUSDT", 1_000.)]
+            .into_iter()
+            .map(|(symbol, volume)| (symbol.into(), volume))
+            .collect();
+        assert_eq!(
+            names(&discovery.triangles(&symbols)),
+            [("BTC", "USDT", "ADA")]
+        );
+    }
+}
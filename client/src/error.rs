@@ -0,0 +1,98 @@
+use crate::api::{
+    error_code::BinanceErrorCode,
+    ws::{RequestId, WsResponse},
+};
+use awc::error::{SendRequestError, WsClientError, WsProtocolError};
+use std::time::Duration;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("connection error: {0}")]
+    Connection(String),
+    #[error("not connected")]
+    NotConnected,
+    #[error("disconnected")]
+    Disconnected,
+    #[error("websocket protocol error: {0}")]
+    Protocol(#[from] WsProtocolError),
+    #[error("serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("query string error: {0}")]
+    QueryString(#[from] serde_qs::Error),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("binance error {} (status {status}): {msg}", code.code())]
+    Api {
+        status: u64,
+        code: BinanceErrorCode,
+        msg: String,
+    },
+    #[error("binance responded with status {status} and no result")]
+    MissingResult { status: u64 },
+    #[error("no request with id {0} in flight")]
+    UnknownRequest(RequestId),
+    #[error("request {0} was lost on reconnect")]
+    Lost(RequestId),
+    #[error("timed out")]
+    Timeout,
+    #[error("rate limited, retry after {retry_after:?}")]
+    RateLimited { retry_after: Duration },
+    #[error("no trading rules loaded for {0}")]
+    UnknownSymbol(String),
+    #[error("{symbol} order fails {filter}: {reason}")]
+    Filter {
+        symbol: String,
+        filter: &'static str,
+        reason: String,
+    },
+    #[error("order book of {symbol} out of sync, expected update {expected} but got {got}")]
+    OutOfSync {
+        symbol: String,
+        expected: u64,
+        got: u64,
+    },
+}
+
+impl From<WsClientError> for Error {
+    fn from(err: WsClientError) -> Self {
+        // the awc error holds non Send parts, keep only its description
+        match err {
+            WsClientError::SendRequest(SendRequestError::Timeout) => Error::Timeout,
+            err => Error::Connection(err.to_string()),
+        }
+    }
+}
+
+impl Error {
+    /// The Binance error code, if this is an API error
+    pub fn code(&self) -> Option<BinanceErrorCode> {
+        match self {
+            Error::Api { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// Extract the result of a response, turning a failed one into an error
+    pub(crate) fn from_response<T>(response: WsResponse<T>) -> Result<T> {
+        match response {
+            WsResponse {
+                status: 200,
+                error: None,
+                result: Some(result),
+                ..
+            } => Ok(result),
+            WsResponse {
+                status,
+                error: Some(error),
+                ..
+            } => Err(Error::Api {
+                status,
+                code: error.code,
+                msg: error.msg,
+            }),
+            WsResponse { status, .. } => Err(Error::MissingResult { status }),
+        }
+    }
+}
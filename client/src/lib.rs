@@ -1,4 +1,11 @@
 pub mod api;
 pub mod bindings;
+pub mod candles;
 pub mod client;
+pub mod discovery;
+mod error;
+pub mod order_book;
+pub mod tape;
 pub mod triangles;
+
+pub use error::{Error, Result};
@@ -1,6 +1,7 @@
 use anyhow::Error;
 use arbitrage_processing::{
     api::{
+        general::ExchangeInfoRequest,
         order::OrderRequest,
         types::{OrderType, Side},
         BinanceRequest,
@@ -38,7 +39,12 @@ fn build_app<'help>() -> App<'help> {
         .value_name("LEVEL")
         .help("Log level");
 
-    App::new("Speed test").args(&[config, loglevel, amount])
+    let test = Arg::new("test")
+        .short('t')
+        .long("test")
+        .help("Send orders as order.test, without executing them");
+
+    App::new("Speed test").args(&[config, loglevel, amount, test])
 }
 
 #[actix_web::main]
@@ -49,6 +55,7 @@ async fn main() -> Result<(), Error> {
     let config_path = matches.value_of("config").unwrap();
     let loglevel = matches.value_of("loglevel").unwrap();
     let amount = matches.value_of_t("amount").unwrap();
+    let test = matches.is_present("test");
 
     let loglevel = match loglevel {
         "off" => LevelFilter::Off,
@@ -66,11 +73,15 @@ async fn main() -> Result<(), Error> {
 
     log::info!("Client started");
 
-    let config = Config::load(config_path).unwrap();
+    let config = Config::load(config_path)?;
     let api = config.api_key;
     let secret_key = config.secret_key;
     let mut client = BinanceClient::new(config.url);
-    client.connect(Duration::new(20, 0)).await;
+    client.set_request_timeout(Some(Duration::new(10, 0)));
+    client.connect(Duration::new(20, 0)).await?;
+    client
+        .request(ExchangeInfoRequest::symbols(vec!["BTCUSDT".into()]))
+        .await?;
 
     for _ in 0..amount {
         let mut order = OrderRequest::new(
@@ -78,10 +89,12 @@ async fn main() -> Result<(), Error> {
             "BTCUSDT",
             Side::Sell,
             OrderType::Market,
-            Some("0.01".into()),
+            Some("0.01".parse()?),
         );
+        order.test = test;
 
-        secret_key.sign(&mut order);
+        client.symbol_rules().conform(&mut order, None)?;
+        secret_key.sign(&mut order)?;
         client.feed(order.preprocess()?).await?;
     }
 
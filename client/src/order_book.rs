@@ -0,0 +1,237 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::{
+    api::{
+        decimal::{Price, Qty},
+        market::{DepthResponse, DepthUpdate, Level},
+        types::Side,
+    },
+    Error, Result,
+};
+
+/// Diffs kept while waiting for a snapshot, older ones are dropped
+const MAX_BUFFERED: usize = 1024;
+
+/// Local copy of a symbol's order book, built from a `depth` snapshot and the diffs
+/// of the `depth` stream following the procedure from the Binance docs:
+/// diffs are buffered until the snapshot is applied, diffs older than the snapshot
+/// are dropped and a gap in the update ids throws the book away until a new
+/// snapshot arrives.
+#[derive(Clone, Debug)]
+pub struct OrderBook {
+    symbol: String,
+    /// Id of the last update applied, None until a snapshot is
+    last_update_id: Option<u64>,
+    bids: BTreeMap<Price, Qty>,
+    asks: BTreeMap<Price, Qty>,
+    buffer: VecDeque<DepthUpdate>,
+}
+
+impl OrderBook {
+    pub fn new(symbol: &str) -> Self {
+        OrderBook {
+            symbol: symbol.into(),
+            last_update_id: None,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            buffer: VecDeque::new(),
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Whether the book holds a snapshot and every diff since
+    pub fn is_synced(&self) -> bool {
+        self.last_update_id.is_some()
+    }
+
+    pub fn last_update_id(&self) -> Option<u64> {
+        self.last_update_id
+    }
+
+    /// Replace the book with a snapshot and apply the diffs buffered meanwhile. Fails
+    /// if the buffered diffs do not follow the snapshot, a newer one is needed then.
+    pub fn apply_snapshot(&mut self, snapshot: &DepthResponse) -> Result<()> {
+        self.bids = levels(&snapshot.bids);
+        self.asks = levels(&snapshot.asks);
+        self.last_update_id = Some(snapshot.last_update_id);
+
+        while let Some(update) = self.buffer.pop_front() {
+            self.update(update)?;
+        }
+        Ok(())
+    }
+
+    /// Apply a diff, or buffer it while there is no snapshot. A gap in the update ids
+    /// resets the book, it stays unsynced until the next `apply_snapshot`.
+    pub fn update(&mut self, update: DepthUpdate) -> Result<()> {
+        let Some(last_update_id) = self.last_update_id else {
+            if self.buffer.len() == MAX_BUFFERED {
+                self.buffer.pop_front();
+            }
+            self.buffer.push_back(update);
+            return Ok(());
+        };
+        // already part of the snapshot
+        if update.final_update_id <= last_update_id {
+            return Ok(());
+        }
+        if update.first_update_id > last_update_id + 1 {
+            let got = update.first_update_id;
+            self.reset();
+            self.buffer.push_back(update);
+            return Err(Error::OutOfSync {
+                symbol: self.symbol.clone(),
+                expected: last_update_id + 1,
+                got,
+            });
+        }
+
+        apply(&mut self.bids, &update.bids);
+        apply(&mut self.asks, &update.asks);
+        self.last_update_id = Some(update.final_update_id);
+        Ok(())
+    }
+
+    /// Drop the levels and wait for a new snapshot
+    pub fn reset(&mut self) {
+        self.last_update_id = None;
+        self.bids.clear();
+        self.asks.clear();
+        self.buffer.clear();
+    }
+
+    /// Highest bid price and quantity
+    pub fn best_bid(&self) -> Option<(f64, f64)> {
+        self.bids
+            .iter()
+            .next_back()
+            .map(|(price, qty)| (price.to_f64(), qty.to_f64()))
+    }
+
+    /// Lowest ask price and quantity
+    pub fn best_ask(&self) -> Option<(f64, f64)> {
+        self.asks
+            .iter()
+            .next()
+            .map(|(price, qty)| (price.to_f64(), qty.to_f64()))
+    }
+
+    /// Average price a market order of `quantity` would fill at, walking the asks for
+    /// a buy and the bids for a sell. None if the book is unsynced or not deep enough.
+    pub fn average_fill_price(&self, side: Side, quantity: f64) -> Option<f64> {
+        if !self.is_synced() || quantity <= 0. {
+            return None;
+        }
+        let levels: Box<dyn Iterator<Item = (&Price, &Qty)>> = match side {
+            Side::Buy => Box::new(self.asks.iter()),
+            Side::Sell => Box::new(self.bids.iter().rev()),
+        };
+
+        let (mut left, mut cost) = (quantity, 0.);
+        for (price, qty) in levels {
+            let filled = left.min(qty.to_f64());
+            cost += filled * price.to_f64();
+            left -= filled;
+            if left <= 0. {
+                return Some(cost / quantity);
+            }
+        }
+        None
+    }
+}
+
+fn levels(levels: &[Level]) -> BTreeMap<Price, Qty> {
+    let mut book = BTreeMap::new();
+    apply(&mut book, levels);
+    book
+}
+
+fn apply(book: &mut BTreeMap<Price, Qty>, levels: &[Level]) {
+    for &(price, qty) in levels {
+        if qty.is_zero() {
+            book.remove(&price);
+        } else {
+            book.insert(price, qty);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(price: &str, qty: &str) -> Level {
+        (price.parse().unwrap(), qty.parse().unwrap())
+    }
+
+    fn diff(first: u64, last: u64, bids: Vec<Level>, asks: Vec<Level>) -> DepthUpdate {
+        DepthUpdate {
+            event_time: 0,
+            symbol: "BTCUSDT".into(),
+            first_update_id: first,
+            final_update_id: last,
+            bids,
+            asks,
+        }
+    }
+
+    fn snapshot() -> DepthResponse {
+        DepthResponse {
+            last_update_id: 100,
+            bids: vec![level("9.0", "1.0"), level("8.0", "2.0")],
+            asks: vec![level("10.0", "1.0"), level("11.0", "2.0")],
+        }
+    }
+
+    #[test]
+    fn buffered_diffs() {
+        let mut book = OrderBook::new("BTCUSDT");
+        // older than the snapshot
+        book.update(diff(90, 100, vec![level("9.0", "5.0")], vec![]))
+            .unwrap();
+        book.update(diff(95, 102, vec![], vec![level("10.0", "0")]))
+            .unwrap();
+        book.update(diff(103, 103, vec![level("9.5", "1.0")], vec![]))
+            .unwrap();
+        assert!(!book.is_synced());
+
+        book.apply_snapshot(&snapshot()).unwrap();
+        assert_eq!(book.last_update_id(), Some(103));
+        assert_eq!(book.best_bid(), Some((9.5, 1.0)));
+        assert_eq!(book.best_ask(), Some((11.0, 2.0)));
+    }
+
+    #[test]
+    fn gap() {
+        let mut book = OrderBook::new("BTCUSDT");
+        book.apply_snapshot(&snapshot()).unwrap();
+        book.update(diff(101, 101, vec![], vec![])).unwrap();
+
+        let err = book.update(diff(103, 104, vec![], vec![])).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::OutOfSync {
+                expected: 102,
+                got: 103,
+                ..
+            }
+        ));
+        assert!(!book.is_synced());
+        assert_eq!(book.best_bid(), None);
+    }
+
+    #[test]
+    fn average_fill_price() {
+        let mut book = OrderBook::new("BTCUSDT");
+        assert_eq!(book.average_fill_price(Side::Buy, 1.), None);
+        book.apply_snapshot(&snapshot()).unwrap();
+
+        assert_eq!(book.average_fill_price(Side::Buy, 1.), Some(10.));
+        assert_eq!(book.average_fill_price(Side::Buy, 2.), Some(10.5));
+        assert_eq!(book.average_fill_price(Side::Sell, 3.), Some(25. / 3.));
+        assert_eq!(book.average_fill_price(Side::Sell, 4.), None);
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use ahash::AHashMap as HashMap;
+use pyo3::pyclass;
+
+use crate::api::{
+    decimal::{Price, Qty},
+    subscription::{AggTrade, Trade},
+    utils,
+};
+
+#[derive(Clone, Copy, Debug)]
+struct TapeEntry {
+    time: u64,
+    price: f64,
+    qty: f64,
+    /// The buyer was the aggressor
+    buy: bool,
+}
+
+/// Trade flow of a symbol over a short window
+#[pyclass]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TapeStats {
+    /// Volume weighted average price, None without trades in the window
+    #[pyo3(get)]
+    pub vwap: Option<f64>,
+    #[pyo3(get)]
+    pub volume: f64,
+    /// Volume of the trades a buyer took
+    #[pyo3(get)]
+    pub buy_volume: f64,
+    /// Volume of the trades a seller took
+    #[pyo3(get)]
+    pub sell_volume: f64,
+    /// Buy minus sell volume over the volume, from -1 for sells only to 1 for buys only
+    #[pyo3(get)]
+    pub imbalance: f64,
+    #[pyo3(get)]
+    pub trades: usize,
+}
+
+/// Latest trades of a symbol in a ring buffer of fixed capacity. Fed by either the
+/// `trade` or the `aggTrade` stream, their ids do not mix.
+#[derive(Clone, Debug)]
+pub struct TradeTape {
+    capacity: usize,
+    /// Width of the window the stats are computed over, in milliseconds
+    window: u64,
+    trades: VecDeque<TapeEntry>,
+    /// Id of the latest trade recorded
+    last_id: Option<u64>,
+}
+
+impl TradeTape {
+    pub fn new(capacity: usize, window: u64) -> Self {
+        TradeTape {
+            capacity,
+            window,
+            trades: VecDeque::with_capacity(capacity),
+            last_id: None,
+        }
+    }
+
+    /// Record a trade, dropping the oldest one once full. Trades with an id already
+    /// passed are skipped, as when several subscriptions deliver the same stream.
+    pub fn push(&mut self, id: u64, time: u64, price: f64, qty: f64, is_buyer_maker: bool) {
+        if self.capacity == 0 || self.last_id.is_some_and(|last_id| id <= last_id) {
+            return;
+        }
+        self.last_id = Some(id);
+        if self.trades.len() == self.capacity {
+            self.trades.pop_front();
+        }
+        self.trades.push_back(TapeEntry {
+            time,
+            price,
+            qty,
+            buy: !is_buyer_maker,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.trades.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trades.is_empty()
+    }
+
+    /// Stats of the trades within the window ending at `now`, in milliseconds
+    pub fn stats(&self, now: u64) -> TapeStats {
+        let since = now.saturating_sub(self.window);
+        let mut stats = TapeStats::default();
+        let mut notional = 0.;
+        for trade in self
+            .trades
+            .iter()
+            .rev()
+            .take_while(|trade| trade.time >= since)
+        {
+            notional += trade.price * trade.qty;
+            if trade.buy {
+                stats.buy_volume += trade.qty;
+            } else {
+                stats.sell_volume += trade.qty;
+            }
+            stats.trades += 1;
+        }
+        stats.volume = stats.buy_volume + stats.sell_volume;
+        if stats.volume > 0. {
+            stats.vwap = Some(notional / stats.volume);
+            stats.imbalance = (stats.buy_volume - stats.sell_volume) / stats.volume;
+        }
+        stats
+    }
+}
+
+/// Trade tapes of every watched symbol, fed by the `trade` and `aggTrade` streams.
+/// Clones share the same tapes.
+#[derive(Clone, Debug)]
+pub struct Tapes {
+    capacity: usize,
+    window: u64,
+    inner: Arc<Mutex<HashMap<String, TradeTape>>>,
+}
+
+impl Tapes {
+    /// Tapes of `capacity` trades, computing their stats over `window` milliseconds
+    pub fn new(capacity: usize, window: u64) -> Self {
+        Tapes {
+            capacity,
+            window,
+            inner: Default::default(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, TradeTape>> {
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn push(&self, symbol: &str, id: u64, time: u64, price: Price, qty: Qty, is_buyer_maker: bool) {
+        let (price, qty) = (price.to_f64(), qty.to_f64());
+        let mut inner = self.lock();
+        match inner.get_mut(symbol) {
+            Some(tape) => tape.push(id, time, price, qty, is_buyer_maker),
+            None => {
+                let mut tape = TradeTape::new(self.capacity, self.window);
+                tape.push(id, time, price, qty, is_buyer_maker);
+                inner.insert(symbol.into(), tape);
+            }
+        }
+    }
+
+    pub fn record_trade(&self, trade: &Trade) {
+        self.push(
+            &trade.symbol,
+            trade.trade_id,
+            trade.trade_time,
+            trade.price,
+            trade.quantity,
+            trade.is_buyer_maker,
+        );
+    }
+
+    pub fn record_agg_trade(&self, trade: &AggTrade) {
+        self.push(
+            &trade.symbol,
+            trade.agg_trade_id,
+            trade.trade_time,
+            trade.price,
+            trade.quantity,
+            trade.is_buyer_maker,
+        );
+    }
+
+    /// Stats of the symbol's window ending now by the server clock, empty ones if no
+    /// trade was recorded yet
+    pub fn stats(&self, symbol: &str) -> TapeStats {
+        self.lock()
+            .get(symbol)
+            .map(|tape| tape.stats(utils::server_timestamp()))
+            .unwrap_or_default()
+    }
+
+    /// Forget the trades of a symbol no longer watched
+    pub fn remove(&self, symbol: &str) {
+        self.lock().remove(symbol);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_stats() {
+        let mut tape = TradeTape::new(3, 1_000);
+        // pushed out of the ring buffer
+        tape.push(1, 0, 100., 50., false);
+        // outside of the window
+        tape.push(2, 500, 100., 50., false);
+        tape.push(3, 1_500, 10., 1., false);
+        tape.push(4, 2_000, 20., 3., true);
+        // delivered again by another subscription
+        tape.push(4, 2_000, 20., 3., true);
+        tape.push(3, 1_500, 10., 1., false);
+        assert_eq!(tape.len(), 3);
+
+        let stats = tape.stats(2_500);
+        assert_eq!(stats.trades, 2);
+        assert_eq!(stats.volume, 4.);
+        assert_eq!(stats.buy_volume, 1.);
+        assert_eq!(stats.sell_volume, 3.);
+        assert_eq!(stats.vwap, Some(17.5));
+        assert_eq!(stats.imbalance, -0.5);
+
+        assert_eq!(tape.stats(10_000), TapeStats::default());
+    }
+}
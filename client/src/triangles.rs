@@ -3,7 +3,17 @@ use std::{
     hash::{BuildHasher, Hash, Hasher},
 };
 
-use crate::api::{subscription::SubscriptionUpdate, types::Side};
+use pyo3::{IntoPy, PyObject, Python};
+
+use crate::{
+    api::{
+        decimal::{Decimal, Price, Qty},
+        subscription::SubscriptionUpdate,
+        types::Side,
+    },
+    client::Commissions,
+    tape::{TapeStats, Tapes},
+};
 
 #[derive(Debug, Clone)]
 pub struct Triangle {
@@ -27,66 +37,103 @@ impl Hash for Triangle {
     }
 }
 
+/// Handed to Python as the `(base, quote, alt)` tuple `Client.subscribe` takes
+impl IntoPy<PyObject> for Triangle {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        (self.base, self.quote, self.alt).into_py(py)
+    }
+}
+
 impl Triangle {
     pub fn new(base: String, quote: String, alt: String) -> Self {
         Self { base, quote, alt }
     }
+    /// Book ticker streams of the three pairs, plus the quote to USDT pair. Stream
+    /// names take lowercase symbols.
+    pub fn streams(&self) -> Vec<String> {
+        let Triangle { base, quote, alt } = self;
+        let mut pairs = vec![
+            format!("{base}{quote}"),
+            format!("{alt}{base}"),
+            format!("{alt}{quote}"),
+        ];
+        if quote != "USDT" {
+            pairs.push(format!("{quote}USDT"));
+        }
+        pairs
+            .into_iter()
+            .map(|pair| format!("{}@bookTicker", pair.to_lowercase()))
+            .collect()
+    }
+    /// Aggregated trade streams of the three pairs, feeding the trade tapes
+    pub fn trade_streams(&self) -> Vec<String> {
+        let Triangle { base, quote, alt } = self;
+        [
+            format!("{base}{quote}"),
+            format!("{alt}{base}"),
+            format!("{alt}{quote}"),
+        ]
+        .into_iter()
+        .map(|pair| format!("{}@aggTrade", pair.to_lowercase()))
+        .collect()
+    }
+    /// Look for an opportunity trading `bag_amount_quote` of the quote asset, usually
+    /// its free balance up to a cap, that is still profitable after the taker
+    /// commissions of the three legs
     pub fn crunch<S: BuildHasher>(
         &self,
         updates: &HashMap<String, SubscriptionUpdate, S>,
-        bag_amount_usdt: f64,
+        bag_amount_quote: Qty,
+        commissions: &Commissions,
     ) -> Option<(Order, Order, Order)> {
         use Side::*;
 
         // TODO: take const threshold as parameter
         #[allow(non_upper_case_globals)]
-        const wanted_profit_pct: f64 = 0.0018;
+        const wanted_profit_pct: Decimal = Decimal::from_units(180_000);
         let Triangle { base, quote, alt } = self;
-        let bag_amount_quote = if quote != "USDT" {
-            // TODO: convert bag amount usdt to bag amount in quote
-            bag_amount_usdt
-        } else {
-            bag_amount_usdt
-        };
+        if !bag_amount_quote.is_positive() {
+            return None;
+        }
 
-        let base_quote_ask = updates
-            .get(&format!("{base}{quote}"))?
-            .best_ask_price
-            .parse::<f64>()
-            .unwrap();
-        let alt_base_ask = updates
-            .get(&format!("{alt}{base}"))?
-            .best_ask_price
-            .parse::<f64>()
-            .unwrap();
-        let alt_quote_bid = updates
-            .get(&format!("{alt}{quote}"))?
-            .best_bid_price
-            .parse::<f64>()
-            .unwrap();
+        let base_quote_ask = updates.get(&format!("{base}{quote}"))?.best_ask_price;
+        let alt_base_ask = updates.get(&format!("{alt}{base}"))?.best_ask_price;
+        let alt_quote_bid = updates.get(&format!("{alt}{quote}"))?.best_bid_price;
+        if !(base_quote_ask.is_positive() && alt_base_ask.is_positive()) {
+            return None;
+        }
 
+        let fee = |symbol: String| Decimal::ONE.checked_sub(commissions.taker_or_default(&symbol));
         let (base_amt, quote_amt, alt_amt, profit_amt);
-        base_amt = bag_amount_quote / base_quote_ask;
-        alt_amt = base_amt / alt_base_ask;
-        quote_amt = alt_amt * alt_quote_bid;
-        profit_amt = quote_amt;
+        base_amt = bag_amount_quote.checked_div(*base_quote_ask)?;
+        alt_amt = base_amt.checked_div(*alt_base_ask)?;
+        quote_amt = alt_amt
+            .checked_mul(*alt_quote_bid)?
+            .checked_mul(fee(format!("{base}{quote}"))?)?
+            .checked_mul(fee(format!("{alt}{base}"))?)?
+            .checked_mul(fee(format!("{alt}{quote}"))?)?;
+        profit_amt = quote_amt.checked_sub(*bag_amount_quote)?;
 
-        if profit_amt > wanted_profit_pct * bag_amount_quote {
+        if profit_amt > bag_amount_quote.checked_mul(wanted_profit_pct)? {
+            let (alt_amt, base_amt) = (Qty(alt_amt), Qty(base_amt));
             Some((
                 Order {
                     symbol: format!("{alt}{quote}"),
-                    amt: alt_amt.to_string(),
+                    amt: alt_amt,
                     action: Sell,
+                    price: alt_quote_bid,
                 },
                 Order {
                     symbol: format!("{alt}{base}"),
-                    amt: alt_amt.to_string(),
+                    amt: alt_amt,
                     action: Buy,
+                    price: alt_base_ask,
                 },
                 Order {
                     symbol: format!("{base}{quote}"),
-                    amt: base_amt.to_string(),
+                    amt: base_amt,
                     action: Buy,
+                    price: base_quote_ask,
                 },
             ))
         } else {
@@ -98,6 +145,96 @@ impl Triangle {
 #[derive(Debug, Clone)]
 pub struct Order {
     pub symbol: String,
-    pub amt: String,
+    /// Raw amount, rounded to the symbol's lot size before the order is signed
+    pub amt: Qty,
     pub action: Side,
+    /// Book price the amount was computed at
+    pub price: Price,
+}
+
+/// Trade flow a leg needs before firing, read from the trade tapes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlowFilter {
+    /// Least volume traded on the pair within the tape window
+    pub min_volume: f64,
+    /// Largest imbalance towards our side, buyers rushing in ahead of a buy push
+    /// the price away from us
+    pub max_imbalance: f64,
+}
+
+impl FlowFilter {
+    pub fn allows(&self, order: &Order, stats: &TapeStats) -> bool {
+        let imbalance = match order.action {
+            Side::Buy => stats.imbalance,
+            Side::Sell => -stats.imbalance,
+        };
+        stats.volume >= self.min_volume && imbalance <= self.max_imbalance
+    }
+
+    /// Whether every leg of an opportunity passes the filter
+    pub fn allows_legs(&self, legs: &(Order, Order, Order), tapes: &Tapes) -> bool {
+        let (leg1, leg2, leg3) = legs;
+        [leg1, leg2, leg3]
+            .into_iter()
+            .all(|leg| self.allows(leg, &tapes.stats(&leg.symbol)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn updates(quotes: &[(&str, &str, &str)]) -> HashMap<String, SubscriptionUpdate> {
+        quotes
+            .iter()
+            .map(|&(symbol, bid, ask)| {
+                let update = SubscriptionUpdate {
+                    update_id: 1,
+                    symbol: symbol.into(),
+                    best_bid_price: bid.parse().unwrap(),
+                    best_bid_qty: "1".parse().unwrap(),
+                    best_ask_price: ask.parse().unwrap(),
+                    best_ask_qty: "1".parse().unwrap(),
+                };
+                (symbol.into(), update)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn crunch() {
+        let triangle = Triangle::new("BTC".into(), "USDT".into(), "ETH".into());
+        let commissions = Commissions::new();
+        let crunch = |alt_quote_bid| {
+            let updates = updates(&[
+                ("BTCUSDT", "99", "100"),
+                ("ETHBTC", "0.049", "0.05"),
+                ("ETHUSDT", alt_quote_bid, "6"),
+            ]);
+            triangle.crunch(&updates, "100".parse().unwrap(), &commissions)
+        };
+
+        // 1% before the three 0.1% fees
+        let (sell, buy_alt, buy_base) = crunch("5.05").unwrap();
+        assert_eq!(
+            (sell.symbol.as_str(), sell.amt.to_string().as_str()),
+            ("ETHUSDT", "20")
+        );
+        assert_eq!(buy_alt.symbol, "ETHBTC");
+        assert_eq!(
+            (buy_base.symbol.as_str(), buy_base.amt.to_string().as_str()),
+            ("BTCUSDT", "1")
+        );
+
+        // 0.2% before fees is a loss after them
+        assert!(crunch("5.01").is_none());
+        assert!(crunch("4.9").is_none());
+        assert!(triangle
+            .crunch(
+                &updates(&[("BTCUSDT", "99", "100")]),
+                "100".parse().unwrap(),
+                &commissions,
+            )
+            .is_none());
+    }
 }
@@ -2,14 +2,72 @@ use actix::{Actor, StreamHandler};
 use actix_web::{web, App, Error, HttpRequest, HttpResponse, HttpServer};
 use actix_web_actors::ws;
 use arbitrage_processing::api::{
-    order::{OrderRequest, OrderResponse},
-    types::OrderStatus,
+    account::{
+        AccountCommissionRequest, AccountCommissionResponse, AccountStatusResponse, Balance,
+        CommissionDiscount, CommissionRates,
+    },
+    decimal::{Decimal, SCALE},
+    general::{
+        EmptyResponse, ExchangeInfoRequest, ExchangeInfoResponse, SymbolFilter, SymbolInfo,
+        TimeResponse,
+    },
+    market::{Kline, KlinesRequest},
+    order::{Fill, OrderRequest, OrderResponse, OrderResponseFull},
+    types::{ExecutionType, NewOrderRespType, OrderStatus, OrderType, TimeInForce},
+    user_data::{ExecutionReport, ListenKeyResponse, UserDataEvent},
+    utils,
     ws::{WsRequest, WsResponse},
 };
 use serde_json::value::RawValue;
 use std::{thread, time::Duration};
 
 mod subscription;
+mod user_data;
+
+/// Assets of the mock account, any pair of them is a symbol
+const ASSETS: [&str; 5] = ["USDT", "BTC", "ETH", "ADA", "DOGE"];
+
+/// Mock prices and quantities, with every decimal shown as Binance does
+fn decimal<T: From<Decimal>>(value: f64) -> T {
+    Decimal::from_f64(value)
+        .unwrap()
+        .with_decimals(SCALE)
+        .into()
+}
+
+/// Rules of a mock symbol, prices are random between 25 and 50
+fn symbol_info(base: &str, quote: &str) -> SymbolInfo {
+    SymbolInfo {
+        symbol: format!("{base}{quote}"),
+        status: "TRADING".into(),
+        base_asset: base.into(),
+        base_asset_precision: 8,
+        quote_asset: quote.into(),
+        quote_asset_precision: 8,
+        order_types: vec![OrderType::Limit, OrderType::Market, OrderType::LimitMaker],
+        is_spot_trading_allowed: true,
+        filters: vec![
+            SymbolFilter::PriceFilter {
+                min_price: "0.01000000".into(),
+                max_price: "1000000.00000000".into(),
+                tick_size: "0.01000000".into(),
+            },
+            SymbolFilter::LotSize {
+                min_qty: "0.00010000".into(),
+                max_qty: "9000.00000000".into(),
+                step_size: "0.00010000".into(),
+            },
+            SymbolFilter::Notional {
+                min_notional: "5.00000000".into(),
+                apply_min_to_market: true,
+                max_notional: "9000000.00000000".into(),
+                apply_max_to_market: false,
+                avg_price_mins: 5,
+            },
+        ],
+        permissions: vec!["SPOT".into()],
+    }
+}
 
 #[derive(Default)]
 struct MyWs {}
@@ -30,21 +88,274 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for MyWs {
                     "order.place" => {
                         let params =
                             serde_json::from_str::<OrderRequest>(request.params.get()).unwrap();
+                        let order_id = rand::random::<u32>().into();
+                        let client_order_id = params
+                            .new_client_order_id
+                            .map(|id| id.to_string())
+                            .unwrap_or_else(|| format!("mock{order_id}"));
+                        let qty = params.quantity.unwrap_or_default();
+                        let price = params.price.unwrap_or_else(|| "1.0".parse().unwrap());
+                        let zero = Decimal::ZERO.with_decimals(SCALE);
+                        // report the fill before replying, as the exchange may do
+                        user_data::push(&UserDataEvent::ExecutionReport(ExecutionReport {
+                            event_time: utils::timestamp(),
+                            symbol: params.symbol.clone(),
+                            client_order_id: client_order_id.clone(),
+                            side: params.side,
+                            order_type: params.order_type,
+                            time_in_force: TimeInForce::Gtc,
+                            quantity: qty,
+                            price: zero.into(),
+                            stop_price: zero.into(),
+                            order_list_id: -1,
+                            orig_client_order_id: String::new(),
+                            execution_type: ExecutionType::Trade,
+                            order_status: OrderStatus::Filled,
+                            reject_reason: "NONE".into(),
+                            order_id,
+                            last_executed_qty: qty,
+                            cumulative_filled_qty: qty,
+                            last_executed_price: price,
+                            commission: zero.into(),
+                            commission_asset: Some("BNB".into()),
+                            transaction_time: params.timestamp,
+                            trade_id: order_id as i64,
+                            is_maker: false,
+                            cumulative_quote_qty: qty,
+                        }));
+                        let text = match params.new_order_resp_type {
+                            NewOrderRespType::Full => {
+                                let response = WsResponse {
+                                    id: request.id,
+                                    status: 200,
+                                    error: None,
+                                    result: Some(OrderResponseFull {
+                                        symbol: params.symbol,
+                                        order_id,
+                                        order_list_id: -1,
+                                        client_order_id,
+                                        transact_time: params.timestamp,
+                                        price,
+                                        orig_qty: qty,
+                                        executed_qty: qty,
+                                        cummulative_quote_qty: qty,
+                                        status: Some(OrderStatus::Filled),
+                                        time_in_force: Some(TimeInForce::Gtc),
+                                        order_type: Some(params.order_type),
+                                        side: Some(params.side),
+                                        fills: vec![Fill {
+                                            price,
+                                            qty,
+                                            commission: Default::default(),
+                                            commission_asset: "BNB".into(),
+                                            trade_id: order_id as i64,
+                                        }],
+                                    }),
+                                    rate_limits: Vec::new(),
+                                };
+                                serde_json::to_string(&response).unwrap()
+                            }
+                            _ => {
+                                let response = WsResponse {
+                                    id: request.id,
+                                    status: 200,
+                                    error: None,
+                                    result: Some(OrderResponse {
+                                        symbol: params.symbol,
+                                        order_id,
+                                        client_order_id,
+                                        transact_time: params.timestamp,
+                                        price: params.price,
+                                        orig_qty: params.quantity,
+                                        executed_qty: params.quantity,
+                                        cummulative_quote_qty: None,
+                                        status: Some(OrderStatus::Filled),
+                                    }),
+                                    rate_limits: Vec::new(),
+                                };
+                                serde_json::to_string(&response).unwrap()
+                            }
+                        };
+                        // println!("sending {text}");
+                        ctx.text(text)
+                    }
+                    "order.test" => {
+                        // validate the parameters, test orders are never executed
+                        serde_json::from_str::<OrderRequest>(request.params.get()).unwrap();
                         let response = WsResponse {
                             id: request.id,
                             status: 200,
                             error: None,
-                            result: OrderResponse {
+                            result: Some(serde_json::json!({})),
+                            rate_limits: Vec::new(),
+                        };
+                        ctx.text(serde_json::to_string(&response).unwrap())
+                    }
+                    "account.status" => {
+                        let balances = ["USDT", "BTC", "ETH", "ADA", "DOGE"]
+                            .into_iter()
+                            .map(|asset| Balance {
+                                asset: asset.into(),
+                                free: "1000.00000000".parse().unwrap(),
+                                locked: Decimal::ZERO.with_decimals(SCALE).into(),
+                            })
+                            .collect();
+                        let response = WsResponse {
+                            id: request.id,
+                            status: 200,
+                            error: None,
+                            result: Some(AccountStatusResponse {
+                                maker_commission: 10,
+                                taker_commission: 10,
+                                buyer_commission: 0,
+                                seller_commission: 0,
+                                can_trade: true,
+                                can_withdraw: true,
+                                can_deposit: true,
+                                update_time: utils::timestamp(),
+                                account_type: "SPOT".into(),
+                                balances,
+                                permissions: vec!["SPOT".into()],
+                            }),
+                            rate_limits: Vec::new(),
+                        };
+                        ctx.text(serde_json::to_string(&response).unwrap())
+                    }
+                    "account.commission" => {
+                        let params =
+                            serde_json::from_str::<AccountCommissionRequest>(request.params.get())
+                                .unwrap();
+                        let zero = Decimal::ZERO.with_decimals(SCALE);
+                        let rates = |taker: &str| CommissionRates {
+                            maker: "0.00100000".parse().unwrap(),
+                            taker: taker.parse().unwrap(),
+                            buyer: zero,
+                            seller: zero,
+                        };
+                        let response = WsResponse {
+                            id: request.id,
+                            status: 200,
+                            error: None,
+                            result: Some(AccountCommissionResponse {
                                 symbol: params.symbol,
-                                transact_time: params.timestamp,
-                                price: params.price,
-                                orig_qty: params.quantity.clone(),
-                                executed_qty: params.quantity.clone(),
-                                cummulative_quote_qty: None,
-                                status: Some(OrderStatus::Filled),
-                            },
+                                standard_commission: rates("0.00100000"),
+                                tax_commission: rates("0.00000000"),
+                                discount: CommissionDiscount {
+                                    enabled_for_account: false,
+                                    enabled_for_symbol: false,
+                                    discount_asset: "BNB".into(),
+                                    discount: "0.75000000".into(),
+                                },
+                            }),
+                            rate_limits: Vec::new(),
+                        };
+                        ctx.text(serde_json::to_string(&response).unwrap())
+                    }
+                    "userDataStream.start" => {
+                        let response = WsResponse {
+                            id: request.id,
+                            status: 200,
+                            error: None,
+                            result: Some(ListenKeyResponse {
+                                listen_key: format!("mock{}", rand::random::<u64>()),
+                            }),
+                            rate_limits: Vec::new(),
+                        };
+                        ctx.text(serde_json::to_string(&response).unwrap())
+                    }
+                    "userDataStream.ping" | "userDataStream.stop" => {
+                        let response = WsResponse {
+                            id: request.id,
+                            status: 200,
+                            error: None,
+                            result: Some(EmptyResponse {}),
+                            rate_limits: Vec::new(),
+                        };
+                        ctx.text(serde_json::to_string(&response).unwrap())
+                    }
+                    "klines" | "uiKlines" => {
+                        let params =
+                            serde_json::from_str::<KlinesRequest>(request.params.get()).unwrap();
+                        // a month for the monthly candles
+                        let interval = params.interval.millis().unwrap_or(2_592_000_000);
+                        let limit = params.limit.unwrap_or(500).clamp(1, 1000) as u64;
+                        let first = match params.start_time {
+                            Some(start) => start - start % interval,
+                            None => {
+                                let end = params.end_time.unwrap_or_else(utils::timestamp);
+                                end - end % interval - (limit - 1) * interval
+                            }
+                        };
+                        // random walk
+                        let mut price = 30.;
+                        let klines = (0..limit)
+                            .map(|i| {
+                                let open = price;
+                                price *= 1. + (rand::random::<f64>() - 0.5) / 50.;
+                                let (high, low) =
+                                    (open.max(price) * 1.001, open.min(price) * 0.999);
+                                let volume = rand::random::<f64>() * 100.;
+                                Kline {
+                                    open_time: first + i * interval,
+                                    open: decimal(open),
+                                    high: decimal(high),
+                                    low: decimal(low),
+                                    close: decimal(price),
+                                    volume: decimal(volume),
+                                    close_time: first + (i + 1) * interval - 1,
+                                    quote_volume: decimal(volume * price),
+                                    trades: 10,
+                                    taker_buy_volume: decimal(volume / 2.),
+                                    taker_buy_quote_volume: decimal(volume * price / 2.),
+                                }
+                            })
+                            .collect::<Vec<_>>();
+                        let response = WsResponse {
+                            id: request.id,
+                            status: 200,
+                            error: None,
+                            result: Some(klines),
+                            rate_limits: Vec::new(),
+                        };
+                        ctx.text(serde_json::to_string(&response).unwrap())
+                    }
+                    "exchangeInfo" => {
+                        let params =
+                            serde_json::from_str::<ExchangeInfoRequest>(request.params.get())
+                                .unwrap();
+                        let mut symbols = ASSETS
+                            .into_iter()
+                            .flat_map(|base| ASSETS.map(|quote| (base, quote)))
+                            .filter(|(base, quote)| base != quote)
+                            .map(|(base, quote)| symbol_info(base, quote))
+                            .collect::<Vec<_>>();
+                        if let Some(wanted) = params.symbols {
+                            symbols.retain(|info| wanted.contains(&info.symbol));
+                        }
+                        let response = WsResponse {
+                            id: request.id,
+                            status: 200,
+                            error: None,
+                            result: Some(ExchangeInfoResponse {
+                                timezone: "UTC".into(),
+                                server_time: utils::timestamp(),
+                                rate_limits: Vec::new(),
+                                symbols,
+                            }),
+                            rate_limits: Vec::new(),
+                        };
+                        ctx.text(serde_json::to_string(&response).unwrap())
+                    }
+                    "time" => {
+                        let response = WsResponse {
+                            id: request.id,
+                            status: 200,
+                            error: None,
+                            result: Some(TimeResponse {
+                                server_time: utils::timestamp(),
+                            }),
+                            rate_limits: Vec::new(),
                         };
-                        // println!("sending {response:#?}");
                         ctx.text(serde_json::to_string(&response).unwrap())
                     }
                     method => panic!("invalid method: {method}"),
@@ -69,7 +380,9 @@ async fn main() -> std::io::Result<()> {
     HttpServer::new(|| {
         App::new()
             .route("/", web::get().to(index))
-            .route("/stream", web::get().to(subscription::stream))
+            .route("/ws", web::get().to(subscription::raw))
+            .route("/stream", web::get().to(subscription::combined))
+            .route("/ws/{listen_key}", web::get().to(user_data::stream))
     })
     .bind((ip, port))?
     .run()
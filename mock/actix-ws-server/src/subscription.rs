@@ -4,23 +4,49 @@ use actix::{Actor, ActorContext, AsyncContext, SpawnHandle, StreamHandler};
 use actix_web::{web, Error, HttpRequest, HttpResponse};
 use actix_web_actors::ws::{self, WsResponseBuilder};
 use arbitrage_processing::api::{
-    subscription::{SubscribeRequest, SubscribeResponse, SubscriptionUpdate},
-    ws::WsRequest,
+    decimal::{Decimal, SCALE},
+    subscription::{
+        AggTrade, KlineUpdate, ListSubscriptionsResponse, StreamEnvelope, StreamKline,
+        SubscribeRequest, SubscribeResponse, SubscriptionUpdate, Trade, UnsubscribeRequest,
+        UnsubscribeResponse,
+    },
+    types::KlineInterval,
+    utils,
+    ws::{StreamResponse, WsRequest},
 };
-use rand::{distributions::Uniform, prelude::Distribution, rngs::StdRng, SeedableRng};
+use rand::{distributions::Uniform, prelude::Distribution, rngs::StdRng, Rng, SeedableRng};
+use serde::Deserialize;
 use serde_json::value::RawValue;
 
 struct Subscriptions {
     rng: StdRng,
+    /// Wrap the payloads in a `StreamEnvelope`, as the `/stream` endpoint does
+    combined: bool,
+    /// Streams given in the url, subscribed once started
+    initial: Vec<String>,
+    /// Ticker of each subscribed stream
     subscriptions: HashMap<String, SpawnHandle>,
 }
-impl Default for Subscriptions {
-    fn default() -> Self {
+
+impl Subscriptions {
+    fn new(combined: bool, initial: Vec<String>) -> Self {
         Self {
             rng: StdRng::from_entropy(),
+            combined,
+            initial,
             subscriptions: Default::default(),
         }
     }
+
+    fn subscribe(&mut self, stream: String, ctx: &mut ws::WebsocketContext<Self>) {
+        let handle = ctx.run_interval(
+            Duration::from_millis(Uniform::new_inclusive(400, 600).sample(&mut self.rng)),
+            coin_ticker(stream.clone()),
+        );
+        if let Some(previous) = self.subscriptions.insert(stream, handle) {
+            ctx.cancel_future(previous);
+        }
+    }
 }
 
 impl Drop for Subscriptions {
@@ -37,6 +63,9 @@ impl Actor for Subscriptions {
         ctx.run_interval(Duration::from_secs(5), |_act, ctx| {
             ctx.ping(b"PING");
         });
+        for stream in std::mem::take(&mut self.initial) {
+            self.subscribe(stream, ctx);
+        }
     }
 
     fn stopping(&mut self, _ctx: &mut Self::Context) -> actix::Running {
@@ -57,25 +86,41 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for Subscriptions {
                     "SUBSCRIBE" => {
                         let params =
                             serde_json::from_str::<SubscribeRequest>(request.params.get()).unwrap();
-                        let response = SubscribeResponse;
                         println!("subscribing to {params:?}");
-                        self.subscriptions
-                            .extend(params.symbols.into_iter().map(|symbol| {
-                                if let Some((symbol, _ty)) = symbol.split_once('@') {
-                                    (
-                                        symbol.to_string(),
-                                        ctx.run_interval(
-                                            Duration::from_millis(
-                                                Uniform::new_inclusive(400, 600)
-                                                    .sample(&mut self.rng),
-                                            ),
-                                            coin_ticker(symbol.to_string()),
-                                        ),
-                                    )
-                                } else {
-                                    todo!("error handling");
-                                }
-                            }));
+                        if let Some(invalid) = params.symbols.iter().find(|s| !s.contains('@')) {
+                            let response = StreamResponse::<SubscribeResponse>::err(
+                                request.id,
+                                2,
+                                format!("Invalid request: invalid stream name {invalid}"),
+                            );
+                            ctx.text(serde_json::to_string(&response).unwrap());
+                            return;
+                        }
+                        for stream in params.symbols {
+                            self.subscribe(stream, ctx);
+                        }
+                        let response = StreamResponse::<SubscribeResponse>::ok(request.id, None);
+                        ctx.text(serde_json::to_string(&response).unwrap());
+                    }
+                    "UNSUBSCRIBE" => {
+                        let params =
+                            serde_json::from_str::<UnsubscribeRequest>(request.params.get())
+                                .unwrap();
+                        for stream in params.symbols {
+                            if let Some(handle) = self.subscriptions.remove(&stream) {
+                                ctx.cancel_future(handle);
+                            }
+                        }
+                        let response = StreamResponse::<UnsubscribeResponse>::ok(request.id, None);
+                        ctx.text(serde_json::to_string(&response).unwrap());
+                    }
+                    "LIST_SUBSCRIPTIONS" => {
+                        let response = StreamResponse::ok(
+                            request.id,
+                            Some(ListSubscriptionsResponse {
+                                streams: self.subscriptions.keys().cloned().collect(),
+                            }),
+                        );
                         ctx.text(serde_json::to_string(&response).unwrap());
                     }
                     method => panic!("invalid method: {method}"),
@@ -90,17 +135,57 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for Subscriptions {
     }
 }
 
-pub async fn stream(req: HttpRequest, stream: web::Payload) -> Result<HttpResponse, Error> {
-    let (addr, response) =
-        WsResponseBuilder::new(Subscriptions::default(), &req, stream).start_with_addr()?;
+#[derive(Deserialize)]
+pub struct StreamsQuery {
+    /// Streams separated by `/`
+    streams: Option<String>,
+}
+
+/// Raw payloads of the subscribed streams, like `/ws`
+pub async fn raw(req: HttpRequest, stream: web::Payload) -> Result<HttpResponse, Error> {
+    start(Subscriptions::new(false, Vec::new()), &req, stream)
+}
+
+/// Payloads wrapped with their stream name, like `/stream?streams=a@bookTicker/b@bookTicker`
+pub async fn combined(
+    req: HttpRequest,
+    query: web::Query<StreamsQuery>,
+    stream: web::Payload,
+) -> Result<HttpResponse, Error> {
+    let initial = query
+        .streams
+        .as_deref()
+        .map(|streams| streams.split('/').map(String::from).collect())
+        .unwrap_or_default();
+    start(Subscriptions::new(true, initial), &req, stream)
+}
+
+fn start(
+    subscriptions: Subscriptions,
+    req: &HttpRequest,
+    stream: web::Payload,
+) -> Result<HttpResponse, Error> {
+    let (addr, response) = WsResponseBuilder::new(subscriptions, req, stream).start_with_addr()?;
     // leak the actor handle, allowing it to persist
     std::mem::forget(addr);
     Ok(response)
 }
 
+/// Mock prices and quantities, with every decimal shown as Binance does
+fn decimal<T: From<Decimal>>(value: f64) -> T {
+    Decimal::from_f64(value)
+        .unwrap()
+        .with_decimals(SCALE)
+        .into()
+}
+
 fn coin_ticker(
-    symbol: String,
+    stream: String,
 ) -> impl FnMut(&mut Subscriptions, &mut ws::WebsocketContext<Subscriptions>) + 'static {
+    // stream names hold lowercase symbols while payloads hold uppercase ones
+    let (symbol, kind) = stream.split_once('@').unwrap_or((&stream, "bookTicker"));
+    let (symbol, kind) = (symbol.to_uppercase(), kind.to_string());
+    let mut trade_id = 0;
     move |act, ctx| {
         let qty_dist = Uniform::new(10., 200.);
         let bid_dist = Uniform::new(25., 50.);
@@ -108,15 +193,77 @@ fn coin_ticker(
 
         let bid = bid_dist.sample(&mut act.rng);
         let ask = bid + ask_diff_dist.sample(&mut act.rng);
-        let resp = SubscriptionUpdate {
-            update_id: 0,
-            symbol: symbol.clone(),
-            best_bid_price: format!("{:.8}", bid),
-            best_bid_qty: format!("{:.8}", qty_dist.sample(&mut act.rng)),
-            best_ask_price: format!("{:.8}", ask),
-            best_ask_qty: format!("{:.8}", qty_dist.sample(&mut act.rng)),
-        };
+        let qty = qty_dist.sample(&mut act.rng);
+        let is_buyer_maker = act.rng.gen();
+        let price = if is_buyer_maker { bid } else { ask };
+        let time = utils::timestamp();
+        trade_id += 1;
+        let resp = match kind.as_str() {
+            "trade" => serde_json::to_value(Trade {
+                event_time: time,
+                symbol: symbol.clone(),
+                trade_id,
+                price: decimal(price),
+                quantity: decimal(qty),
+                trade_time: time,
+                is_buyer_maker,
+            }),
+            "aggTrade" => serde_json::to_value(AggTrade {
+                event_time: time,
+                symbol: symbol.clone(),
+                agg_trade_id: trade_id,
+                price: decimal(price),
+                quantity: decimal(qty),
+                first_trade_id: trade_id,
+                last_trade_id: trade_id,
+                trade_time: time,
+                is_buyer_maker,
+            }),
+            kind if kind.starts_with("kline_") => {
+                let interval = KlineInterval::parse(&kind["kline_".len()..]).unwrap();
+                let length = interval.millis().unwrap_or(2_592_000_000);
+                let open_time = time - time % length;
+                let mid = decimal((bid + ask) / 2.);
+                serde_json::to_value(KlineUpdate {
+                    event_time: time,
+                    symbol: symbol.clone(),
+                    kline: StreamKline {
+                        open_time,
+                        close_time: open_time + length - 1,
+                        interval,
+                        first_trade_id: 0,
+                        last_trade_id: trade_id as i64,
+                        open: mid,
+                        close: mid,
+                        high: decimal(ask),
+                        low: decimal(bid),
+                        volume: decimal(qty),
+                        trades: trade_id,
+                        is_closed: false,
+                        quote_volume: decimal(qty * price),
+                    },
+                })
+            }
+            _ => serde_json::to_value(SubscriptionUpdate {
+                update_id: 0,
+                symbol: symbol.clone(),
+                best_bid_price: decimal(bid),
+                best_bid_qty: decimal(qty),
+                best_ask_price: decimal(ask),
+                best_ask_qty: decimal(qty_dist.sample(&mut act.rng)),
+            }),
+        }
+        .unwrap();
         // println!("sending {resp:#?}");
-        ctx.text(serde_json::to_string_pretty(&resp).unwrap());
+        let text = if act.combined {
+            let envelope = StreamEnvelope {
+                stream: stream.clone(),
+                data: resp,
+            };
+            serde_json::to_string_pretty(&envelope).unwrap()
+        } else {
+            serde_json::to_string_pretty(&resp).unwrap()
+        };
+        ctx.text(text);
     }
 }
@@ -0,0 +1,64 @@
+use std::{sync::Mutex, time::Duration};
+
+use actix::{Actor, ActorContext, Addr, AsyncContext, Handler, Message, StreamHandler};
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use actix_web_actors::ws::{self, WsResponseBuilder};
+use arbitrage_processing::api::user_data::UserDataEvent;
+
+/// Open user data streams, every event is sent to all of them
+static STREAMS: Mutex<Vec<Addr<UserData>>> = Mutex::new(Vec::new());
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Push(String);
+
+struct UserData;
+
+impl Actor for UserData {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        STREAMS.lock().unwrap().push(ctx.address());
+        ctx.run_interval(Duration::from_secs(5), |_act, ctx| {
+            ctx.ping(b"PING");
+        });
+    }
+
+    fn stopped(&mut self, ctx: &mut Self::Context) {
+        let address = ctx.address();
+        STREAMS.lock().unwrap().retain(|other| *other != address);
+    }
+}
+
+impl Handler<Push> for UserData {
+    type Result = ();
+
+    fn handle(&mut self, Push(text): Push, ctx: &mut Self::Context) {
+        ctx.text(text);
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for UserData {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => ctx.pong(&msg),
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Send an event to every open user data stream
+pub fn push(event: &UserDataEvent) {
+    let text = serde_json::to_string(event).unwrap();
+    for stream in STREAMS.lock().unwrap().iter() {
+        stream.do_send(Push(text.clone()));
+    }
+}
+
+pub async fn stream(req: HttpRequest, stream: web::Payload) -> Result<HttpResponse, Error> {
+    WsResponseBuilder::new(UserData, &req, stream).start()
+}